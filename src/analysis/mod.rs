@@ -0,0 +1,1675 @@
+/**
+ * ============================================================================
+ * Markdown Analysis Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Walk a parsed Markdown AST and compute statistics
+ * and other derived information about the document.
+ * ============================================================================
+*/
+use crate::ast::{OwnedNode, from_owned_tree, node_type_name, to_owned_tree};
+use crate::parser::{Arena, Flavor};
+use crate::renderer::{to_html, to_plain_text};
+use comrak::html::collect_text;
+use comrak::nodes::{AlertType, AstNode, NodeValue, TableAlignment};
+use comrak::{Anchorizer, parse_document};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Average adult reading speed used by `default_reading_time`.
+pub const DEFAULT_WORDS_PER_MINUTE: usize = 200;
+
+/// Counts the words in a document.
+///
+/// Words are counted in text, inline code and code blocks,
+/// split on Unicode whitespace. Raw HTML and image alt text are skipped.
+pub fn word_count<'a>(root: &'a AstNode<'a>) -> usize {
+    let words = match root.data.borrow().value {
+        NodeValue::Text(ref text) => count_words(text),
+        NodeValue::Code(ref code) => count_words(&code.literal),
+        NodeValue::CodeBlock(ref block) => count_words(&block.literal),
+        // Alt text lives in the image's children, so don't descend.
+        NodeValue::Image(_) => return 0,
+        _ => 0,
+    };
+
+    words + root.children().map(word_count).sum::<usize>()
+}
+
+/// Estimates how long a document takes to read at `words_per_minute`.
+///
+/// The estimate is rounded up to whole minutes, so any non-empty
+/// document takes at least one minute. Empty documents take no time.
+pub fn reading_time<'a>(root: &'a AstNode<'a>, words_per_minute: usize) -> Duration {
+    let words = word_count(root);
+    let minutes = words.div_ceil(words_per_minute.max(1));
+    Duration::from_secs(minutes as u64 * 60)
+}
+
+/// Estimates reading time at `DEFAULT_WORDS_PER_MINUTE`.
+pub fn default_reading_time<'a>(root: &'a AstNode<'a>) -> Duration {
+    reading_time(root, DEFAULT_WORDS_PER_MINUTE)
+}
+
+/// A heading found in the document, with its GitHub-style anchor slug.
+///
+/// Positions are 1-based source lines and byte columns, inclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// Extracts every heading in document order.
+///
+/// Slugs are generated the way GitHub does: lowercased, spaces turned
+/// into hyphens and punctuation stripped. Repeated slugs get `-1`, `-2`,
+/// ... suffixes. This is the same algorithm comrak uses for header ids,
+/// so the slugs match anchors in rendered HTML.
+pub fn extract_headings<'a>(root: &'a AstNode<'a>) -> Vec<Heading> {
+    let mut anchorizer = Anchorizer::new();
+    let mut headings = Vec::new();
+
+    for node in root.descendants() {
+        if let NodeValue::Heading(ref heading) = node.data.borrow().value {
+            let text = inline_text(node);
+            let slug = anchorizer.anchorize(text.clone());
+            let pos = node.data.borrow().sourcepos;
+            headings.push(Heading {
+                level: heading.level,
+                text,
+                slug,
+                start_line: pos.start.line,
+                start_col: pos.start.column,
+                end_line: pos.end.line,
+                end_col: pos.end.column,
+            });
+        }
+    }
+
+    headings
+}
+
+/// Returns the text of the first h1, with inline formatting flattened,
+/// or `None` if the document has no h1.
+///
+/// Headings nested in block quotes or lists count too, as in
+/// `extract_headings`.
+pub fn document_title<'a>(root: &'a AstNode<'a>) -> Option<String> {
+    root.descendants()
+        .find(|node| matches!(node.data.borrow().value, NodeValue::Heading(ref h) if h.level == 1))
+        .map(inline_text)
+}
+
+/// A hyperlink found in the document, with its 1-based source position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    pub text: String,
+    pub title: Option<String>,
+    /// Whether this is a `[[wikilink]]` rather than a Markdown link.
+    pub wikilink: bool,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// Extracts every link in document order.
+///
+/// Inline, reference-style and autolinks are all captured, since comrak
+/// resolves them to the same kind of node. Bare URLs are only links
+/// under flavors with the autolink extension, such as `Flavor::GitHub`.
+/// Wikilinks are included too when enabled, with `wikilink` set; their
+/// `url` is the page name as written. Reference links already carry the
+/// URL and title of their definition, as in `extract_link_definitions`.
+pub fn extract_links<'a>(root: &'a AstNode<'a>) -> Vec<Link> {
+    root.descendants()
+        .filter_map(|node| {
+            let (url, title, wikilink) = match node.data.borrow().value {
+                NodeValue::Link(ref link) => (link.url.clone(), non_empty(&link.title), false),
+                NodeValue::WikiLink(ref link) => (link.url.clone(), None, true),
+                _ => return None,
+            };
+            let pos = node.data.borrow().sourcepos;
+            Some(Link {
+                url,
+                text: inline_text(node),
+                title,
+                wikilink,
+                start_line: pos.start.line,
+                start_col: pos.start.column,
+                end_line: pos.end.line,
+                end_col: pos.end.column,
+            })
+        })
+        .collect()
+}
+
+/// Extracts the reference link definitions (`[label]: url "title"`)
+/// from Markdown source, keyed by normalized label.
+///
+/// This takes the source rather than an AST because comrak consumes
+/// definitions while parsing and leaves no node for them. Labels are
+/// normalized as for matching: trimmed, with whitespace runs collapsed
+/// and lowercased. When a label is defined twice the first definition
+/// wins, as it does for the links that use it. Definitions with labels
+/// spanning several lines are not found.
+pub fn extract_link_definitions(content: &str) -> HashMap<String, (String, Option<String>)> {
+    let mut definitions = HashMap::new();
+    for (_, label, destination) in resolve_definitions(content) {
+        definitions.entry(label).or_insert(destination);
+    }
+    definitions
+}
+
+/// Returns the normalized labels of link definitions that no link in
+/// `content` refers to, in the order they are defined.
+pub fn unused_link_definitions(content: &str) -> Vec<String> {
+    let definitions = resolve_definitions(content);
+
+    // With the definitions blanked out, every reference becomes broken and
+    // is reported to the callback, whichever of the three forms it takes.
+    let definition_lines: Vec<usize> = definitions.iter().map(|(line, ..)| *line).collect();
+    let blanked: String = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if definition_lines.contains(&i) {
+                ""
+            } else {
+                line
+            }
+        })
+        .flat_map(|line| [line, "\n"])
+        .collect();
+
+    let referenced = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&referenced);
+    let mut options = Flavor::CommonMark.to_options();
+    options.parse.broken_link_callback =
+        Some(Arc::new(move |reference: comrak::BrokenLinkReference| {
+            sink.lock()
+                .unwrap()
+                .push(normalize_label(reference.original));
+            None
+        }));
+    parse_document(&Arena::new(), &blanked, &options);
+
+    let referenced = referenced.lock().unwrap();
+    let mut unused: Vec<String> = Vec::new();
+    for (_, label, _) in definitions {
+        if !referenced.contains(&label) && !unused.contains(&label) {
+            unused.push(label);
+        }
+    }
+    unused
+}
+
+/// Finds every line of `content` holding a definition, as (line index,
+/// normalized label, destination) in source order.
+///
+/// Candidate labels are found by scanning lines, then confirmed and
+/// resolved by comrak itself: each is parsed as a shortcut reference
+/// `[label]` ahead of the document, so lines that merely look like
+/// definitions, such as ones in code blocks, only resolve if the label
+/// is also defined for real. Either way they resolve to the definition
+/// that wins.
+fn resolve_definitions(content: &str) -> Vec<(usize, String, (String, Option<String>))> {
+    let candidates: Vec<(usize, &str)> = content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (label, _) = line.trim_start().strip_prefix('[')?.split_once("]:")?;
+            // `[^1]:` is a footnote, not a link.
+            let valid = !label.trim().is_empty() && !label.starts_with('^') && !label.contains('[');
+            valid.then_some((i, label))
+        })
+        .collect();
+
+    let probes: String = candidates
+        .iter()
+        .map(|(_, label)| format!("[{}]\n\n", label))
+        .collect();
+    let arena = Arena::new();
+    let root = parse_document(
+        &arena,
+        &(probes + content),
+        &Flavor::CommonMark.to_options(),
+    );
+
+    candidates
+        .into_iter()
+        .zip(root.children())
+        .filter_map(|((line, label), probe)| {
+            let link = probe.first_child()?;
+            match link.data.borrow().value {
+                NodeValue::Link(ref link) => Some((
+                    line,
+                    normalize_label(label),
+                    (link.url.clone(), non_empty(&link.title)),
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// An image referenced by the document, with its 1-based source position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Image {
+    pub url: String,
+    pub alt: String,
+    pub title: Option<String>,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// Extracts every image in document order.
+/// The destination is captured verbatim, whether relative or absolute.
+pub fn extract_images<'a>(root: &'a AstNode<'a>) -> Vec<Image> {
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::Image(ref image) => {
+                let pos = node.data.borrow().sourcepos;
+                Some(Image {
+                    url: image.url.clone(),
+                    alt: inline_text(node),
+                    title: non_empty(&image.title),
+                    start_line: pos.start.line,
+                    start_col: pos.start.column,
+                    end_line: pos.end.line,
+                    end_col: pos.end.column,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A fenced or indented code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub literal: String,
+    pub fenced: bool,
+    /// The fence's info string, parsed into its directives.
+    pub info: CodeBlockInfo,
+}
+
+/// The directives in a code fence's info string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeBlockInfo {
+    pub language: Option<String>,
+    pub flags: Vec<String>,
+    pub attributes: HashMap<String, String>,
+}
+
+impl CodeBlockInfo {
+    /// Parses an info string such as `rust,no_run` or
+    /// `python {.numberLines startFrom="5"}`.
+    ///
+    /// Outside braces, the first word is the language and any further
+    /// words, separated by commas or whitespace, are flags. Inside a
+    /// Pandoc-style `{ }` block, `.class` and bare words become flags,
+    /// `#id` becomes an `id` attribute and `key=value` an attribute with
+    /// any surrounding quotes removed. With no language before the
+    /// braces, the first class names the language, as in Pandoc.
+    pub fn parse(info: &str) -> Self {
+        let (plain, braced) = match info.split_once('{') {
+            Some((plain, rest)) => (
+                plain,
+                rest.rsplit_once('}').map_or(rest, |(inner, _)| inner),
+            ),
+            None => (info, ""),
+        };
+
+        let mut words = plain
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string);
+        let mut parsed = CodeBlockInfo {
+            language: words.next(),
+            flags: words.collect(),
+            attributes: HashMap::new(),
+        };
+
+        for token in attribute_tokens(braced) {
+            if let Some(class) = token.strip_prefix('.') {
+                if parsed.language.is_none() {
+                    parsed.language = Some(class.to_string());
+                } else {
+                    parsed.flags.push(class.to_string());
+                }
+            } else if let Some(id) = token.strip_prefix('#') {
+                parsed.attributes.insert("id".to_string(), id.to_string());
+            } else if let Some((key, value)) = token.split_once('=') {
+                let value = value.trim_matches(['"', '\'']);
+                parsed.attributes.insert(key.to_string(), value.to_string());
+            } else {
+                parsed.flags.push(token);
+            }
+        }
+
+        parsed
+    }
+}
+
+/// Splits a `{ }` attribute block on whitespace outside of quotes.
+fn attribute_tokens(attributes: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quote = None;
+    for c in attributes.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                token.push(c);
+            }
+            (Some(open), _) if c == open => {
+                quote = None;
+                token.push(c);
+            }
+            (None, _) if c.is_whitespace() || c == ',' => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            _ => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Extracts every code block in document order.
+///
+/// The `language` is that of the parsed `info` string, so usually its
+/// first word. Indented code blocks have no info string, so their
+/// language is `None`.
+pub fn extract_code_blocks<'a>(root: &'a AstNode<'a>) -> Vec<CodeBlock> {
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::CodeBlock(ref block) => {
+                let info = CodeBlockInfo::parse(&block.info);
+                Some(CodeBlock {
+                    language: info.language.clone(),
+                    literal: block.literal.clone(),
+                    fenced: block.fenced,
+                    info,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Extracts the code blocks whose language is `language`, ignoring
+/// ASCII case, in document order.
+///
+/// Blocks without a language, such as indented code, never match.
+pub fn extract_code_blocks_by_language<'a>(
+    root: &'a AstNode<'a>,
+    language: &str,
+) -> Vec<CodeBlock> {
+    extract_code_blocks(root)
+        .into_iter()
+        .filter(|block| {
+            block
+                .language
+                .as_deref()
+                .is_some_and(|lang| lang.eq_ignore_ascii_case(language))
+        })
+        .collect()
+}
+
+/// Counts fenced code blocks per language, lowercased.
+///
+/// Fenced blocks without a language are counted under `""`. Indented
+/// code blocks are left out, since they can't name a language at all.
+pub fn code_language_histogram<'a>(root: &'a AstNode<'a>) -> HashMap<String, usize> {
+    let mut histogram = HashMap::new();
+    for block in extract_code_blocks(root)
+        .into_iter()
+        .filter(|block| block.fenced)
+    {
+        let language = block.language.unwrap_or_default().to_lowercase();
+        *histogram.entry(language).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// A footnote definition referenced from the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footnote {
+    pub label: String,
+    pub text: String,
+}
+
+/// Extracts every referenced footnote with its definition's text.
+///
+/// Requires the footnotes extension. Orphaned references with no
+/// matching definition are skipped: comrak leaves them as plain text,
+/// so they never become footnote nodes.
+pub fn extract_footnotes<'a>(root: &'a AstNode<'a>) -> Vec<Footnote> {
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::FootnoteDefinition(ref definition) => Some(Footnote {
+                label: definition.name.clone(),
+                text: to_plain_text(node),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// An item of a GFM task list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskItem {
+    pub checked: bool,
+    pub text: String,
+}
+
+/// Extracts every task list item in document order.
+///
+/// Requires the tasklist extension, which `Flavor::GitHub` enables.
+/// Items marked `[x]` or `[X]` are checked and `[ ]` items are not. The
+/// text is that of the item's first paragraph, so nested sub-tasks are
+/// reported as items of their own instead.
+pub fn extract_tasks<'a>(root: &'a AstNode<'a>) -> Vec<TaskItem> {
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::TaskItem(symbol) => Some(TaskItem {
+                checked: matches!(symbol, Some('x' | 'X')),
+                text: node.first_child().map(inline_text).unwrap_or_default(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The alignment of a table column, from its delimiter row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl From<TableAlignment> for Alignment {
+    fn from(alignment: TableAlignment) -> Self {
+        match alignment {
+            TableAlignment::None => Alignment::None,
+            TableAlignment::Left => Alignment::Left,
+            TableAlignment::Center => Alignment::Center,
+            TableAlignment::Right => Alignment::Right,
+        }
+    }
+}
+
+/// A GFM table with its cells flattened to plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub alignments: Vec<Alignment>,
+}
+
+/// Extracts every table in document order.
+///
+/// Requires the table extension, which `Flavor::GitHub` enables. Every
+/// row has exactly one cell per header: short rows are padded with empty
+/// strings and cells beyond the header count are dropped, matching how
+/// GFM renders ragged tables.
+pub fn extract_tables<'a>(root: &'a AstNode<'a>) -> Vec<Table> {
+    root.descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::Table(ref table) => {
+                let mut rows = node
+                    .children()
+                    .map(|row| row.children().map(inline_text).collect::<Vec<_>>());
+                let headers = rows.next().unwrap_or_default();
+                let rows = rows
+                    .map(|mut row| {
+                        row.resize(headers.len(), String::new());
+                        row
+                    })
+                    .collect();
+                Some(Table {
+                    headers,
+                    rows,
+                    alignments: table.alignments.iter().map(|&a| a.into()).collect(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Counts the nodes of each kind in the tree rooted at `root`.
+///
+/// Keys are the stable names from `node_type_name`, such as
+/// `"heading"`, `"paragraph"`, `"link"` or `"code_block"`. The root
+/// itself is counted too, and kinds that don't occur have no entry.
+pub fn node_stats<'a>(root: &'a AstNode<'a>) -> HashMap<&'static str, usize> {
+    let mut stats = HashMap::new();
+    for node in root.descendants() {
+        *stats
+            .entry(node_type_name(&node.data.borrow().value))
+            .or_insert(0) += 1;
+    }
+    stats
+}
+
+/// Extracts every definition list term with its definitions.
+///
+/// Requires the description lists extension, which no built-in flavor
+/// enables; turn it on with `ParseConfigBuilder::description_lists`.
+/// A term followed by several `: Definition` blocks yields a single
+/// entry with all of them, in document order.
+pub fn extract_definitions<'a>(root: &'a AstNode<'a>) -> Vec<(String, Vec<String>)> {
+    let mut definitions: Vec<(String, Vec<String>)> = Vec::new();
+    for item in root.descendants() {
+        if !matches!(item.data.borrow().value, NodeValue::DescriptionItem(_)) {
+            continue;
+        }
+
+        for part in item.children() {
+            match part.data.borrow().value {
+                NodeValue::DescriptionTerm => definitions.push((to_plain_text(part), Vec::new())),
+                // comrak puts each extra definition of a term in an item of its own.
+                NodeValue::DescriptionDetails => match definitions.last_mut() {
+                    Some((_, details)) => details.push(to_plain_text(part)),
+                    None => definitions.push((String::new(), vec![to_plain_text(part)])),
+                },
+                _ => {}
+            }
+        }
+    }
+    definitions
+}
+
+/// A block quote and how deeply it is nested in other block quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blockquote {
+    /// 1 for a top-level quote, 2 for a quote inside it, and so on.
+    pub depth: usize,
+    /// The quote's plain text, including that of any quotes nested in it.
+    pub text: String,
+}
+
+/// Extracts every block quote in document order, outer quotes first.
+pub fn extract_blockquotes<'a>(root: &'a AstNode<'a>) -> Vec<Blockquote> {
+    let is_quote =
+        |node: &&'a AstNode<'a>| matches!(node.data.borrow().value, NodeValue::BlockQuote);
+    root.descendants()
+        .filter(is_quote)
+        .map(|node| Blockquote {
+            // `ancestors` starts with the node itself.
+            depth: node.ancestors().filter(is_quote).count(),
+            text: to_plain_text(node),
+        })
+        .collect()
+}
+
+/// The kinds of GitHub alerts, named after their `[!KIND]` markers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AlertKind {
+    /// Recognizes a marker such as `[!WARNING]`, ignoring case.
+    fn from_marker(marker: &str) -> Option<AlertKind> {
+        let name = marker.trim().strip_prefix("[!")?.strip_suffix(']')?;
+        match name.to_ascii_lowercase().as_str() {
+            "note" => Some(AlertKind::Note),
+            "tip" => Some(AlertKind::Tip),
+            "important" => Some(AlertKind::Important),
+            "warning" => Some(AlertKind::Warning),
+            "caution" => Some(AlertKind::Caution),
+            _ => None,
+        }
+    }
+}
+
+impl From<AlertType> for AlertKind {
+    fn from(alert_type: AlertType) -> Self {
+        match alert_type {
+            AlertType::Note => AlertKind::Note,
+            AlertType::Tip => AlertKind::Tip,
+            AlertType::Important => AlertKind::Important,
+            AlertType::Warning => AlertKind::Warning,
+            AlertType::Caution => AlertKind::Caution,
+        }
+    }
+}
+
+/// A GitHub alert such as `> [!NOTE]`, with its content rendered to HTML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// The content after the marker line, rendered as GFM.
+    pub content_html: String,
+}
+
+/// Extracts every GitHub alert in document order.
+///
+/// Alerts are found whether or not comrak's alerts extension was on
+/// while parsing: with it they are alert nodes, and without it they are
+/// block quotes whose first line is nothing but a marker like `[!TIP]`.
+/// Quotes with any other first line, including unknown markers such as
+/// `[!TODO]`, are ordinary quotes and are skipped.
+pub fn extract_alerts<'a>(root: &'a AstNode<'a>) -> Vec<Alert> {
+    root.descendants()
+        .filter_map(|node| {
+            let (kind, content) = match node.data.borrow().value {
+                NodeValue::Alert(ref alert) => (
+                    AlertKind::from(alert.alert_type),
+                    node.children().map(to_owned_tree).collect(),
+                ),
+                NodeValue::BlockQuote => quote_alert(node)?,
+                _ => return None,
+            };
+            let document = OwnedNode {
+                value: NodeValue::Document,
+                sourcepos: node.data.borrow().sourcepos,
+                children: content,
+            };
+            let arena = Arena::new();
+            Some(Alert {
+                kind,
+                content_html: to_html(from_owned_tree(&document, &arena), Flavor::GitHub),
+            })
+        })
+        .collect()
+}
+
+/// Splits a block quote into its alert kind and the blocks after the
+/// marker line, if it starts with a known marker.
+fn quote_alert<'a>(quote: &'a AstNode<'a>) -> Option<(AlertKind, Vec<OwnedNode>)> {
+    let mut blocks: Vec<OwnedNode> = quote.children().map(to_owned_tree).collect();
+    let first = blocks
+        .first_mut()
+        .filter(|block| block.value == NodeValue::Paragraph)?;
+
+    let line_end = first
+        .children
+        .iter()
+        .position(|inline| matches!(inline.value, NodeValue::SoftBreak | NodeValue::LineBreak))
+        .unwrap_or(first.children.len());
+    let mut marker = String::new();
+    for inline in &first.children[..line_end] {
+        match inline.value {
+            NodeValue::Text(ref text) => marker.push_str(text),
+            _ => return None,
+        }
+    }
+    let kind = AlertKind::from_marker(&marker)?;
+
+    // Drop the marker line along with the break that ends it.
+    first
+        .children
+        .drain(..(line_end + 1).min(first.children.len()));
+    if first.children.is_empty() {
+        blocks.remove(0);
+    }
+    Some((kind, blocks))
+}
+
+/// A heading and the blocks under it, as split by `split_into_sections`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// `None` for the content before the first heading.
+    pub heading: Option<Heading>,
+    pub content: Vec<OwnedNode>,
+}
+
+/// Splits a document into one section per top-level heading.
+///
+/// A section holds every block after its heading up to the next heading
+/// of the same or a higher level, so an h1's section also contains its
+/// h2 subsections, which get sections of their own as well. Blocks
+/// before the first heading form a leading section without a heading,
+/// if there are any. Headings nested in block quotes or lists don't
+/// start sections; they stay part of the content.
+pub fn split_into_sections<'a>(root: &'a AstNode<'a>) -> Vec<Section> {
+    // Look top-level headings up in `extract_headings` by position, so
+    // slugs are deduplicated across the whole document.
+    let headings = extract_headings(root);
+    let blocks: Vec<_> = root.children().collect();
+    let block_headings: Vec<Option<&Heading>> = blocks
+        .iter()
+        .map(|block| {
+            let data = block.data.borrow();
+            if !matches!(data.value, NodeValue::Heading(_)) {
+                return None;
+            }
+            let start = data.sourcepos.start;
+            headings.iter().find(|heading| {
+                heading.start_line == start.line && heading.start_col == start.column
+            })
+        })
+        .collect();
+
+    let mut sections = Vec::new();
+    let first_heading = block_headings
+        .iter()
+        .position(Option::is_some)
+        .unwrap_or(blocks.len());
+    if first_heading > 0 {
+        sections.push(Section {
+            heading: None,
+            content: blocks[..first_heading]
+                .iter()
+                .map(|block| to_owned_tree(block))
+                .collect(),
+        });
+    }
+
+    for (i, heading) in block_headings.iter().enumerate() {
+        let Some(heading) = heading else {
+            continue;
+        };
+        let end = block_headings[i + 1..]
+            .iter()
+            .position(|next| next.is_some_and(|next| next.level <= heading.level))
+            .map_or(blocks.len(), |offset| i + 1 + offset);
+        sections.push(Section {
+            heading: Some((*heading).clone()),
+            content: blocks[i + 1..end]
+                .iter()
+                .map(|block| to_owned_tree(block))
+                .collect(),
+        });
+    }
+
+    sections
+}
+
+/// Default maximum length of `DocumentSummary::description`, in characters.
+pub const DEFAULT_DESCRIPTION_LENGTH: usize = 160;
+
+/// A compact summary of a document for index pages and feeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSummary {
+    /// The text of the first h1.
+    pub title: Option<String>,
+    /// The text of the first top-level paragraph, possibly truncated.
+    pub description: Option<String>,
+    pub word_count: usize,
+    /// The URL of the first image.
+    pub image: Option<String>,
+}
+
+/// Summarizes a document, with the description truncated to
+/// `DEFAULT_DESCRIPTION_LENGTH` characters.
+pub fn summarize<'a>(root: &'a AstNode<'a>) -> DocumentSummary {
+    summarize_with_max_length(root, DEFAULT_DESCRIPTION_LENGTH)
+}
+
+/// Summarizes a document, with the description truncated to at most
+/// `max_length` characters.
+///
+/// The description comes from the first paragraph directly under the
+/// root that has any words outside of images, so text in lists or block
+/// quotes is never picked. Long
+/// descriptions are cut at a word boundary and end in `…`, which
+/// counts towards `max_length`.
+pub fn summarize_with_max_length<'a>(root: &'a AstNode<'a>, max_length: usize) -> DocumentSummary {
+    let title = document_title(root);
+    let description = root
+        .children()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        // Skip paragraphs that only hold images, such as a cover picture.
+        .find(|node| word_count(node) > 0)
+        .map(|node| truncate_words(&to_plain_text(node), max_length));
+
+    DocumentSummary {
+        title,
+        description,
+        word_count: word_count(root),
+        image: extract_images(root)
+            .into_iter()
+            .next()
+            .map(|image| image.url),
+    }
+}
+
+/// A difference between the top-level blocks of two documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeDiff {
+    /// A block only in the new document, at `index` among its blocks.
+    Added { index: usize, text: String },
+    /// A block only in the old document, at `index` among its blocks.
+    Removed { index: usize, text: String },
+    /// A block of the same kind whose text changed.
+    Modified {
+        old_index: usize,
+        new_index: usize,
+        old: String,
+        new: String,
+    },
+}
+
+/// Compares the top-level blocks of two documents.
+///
+/// Blocks are equal when they have the same kind and plain text, so
+/// re-wrapping a paragraph or changing its markup is not a change.
+/// Blocks are matched up with a longest common subsequence; a removed
+/// block directly replaced by an added block of the same kind is
+/// reported as `Modified` instead. Diffs are in document order.
+pub fn diff_documents<'a>(old: &'a AstNode<'a>, new: &'a AstNode<'a>) -> Vec<NodeDiff> {
+    let blocks = |root: &'a AstNode<'a>| -> Vec<(&'static str, String)> {
+        root.children()
+            .map(|node| {
+                (
+                    node_type_name(&node.data.borrow().value),
+                    to_plain_text(node),
+                )
+            })
+            .collect()
+    };
+    let (old, new) = (blocks(old), blocks(new));
+
+    // lcs[i][j] is the LCS length of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diffs = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            pair_changes(&old, &new, &mut removed, &mut added, &mut diffs);
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    pair_changes(&old, &new, &mut removed, &mut added, &mut diffs);
+
+    diffs
+}
+
+/// Flushes one run of removed and added blocks into `diffs`, pairing
+/// blocks of the same kind up as modifications.
+fn pair_changes(
+    old: &[(&'static str, String)],
+    new: &[(&'static str, String)],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    diffs: &mut Vec<NodeDiff>,
+) {
+    let mut added_iter = added.drain(..).peekable();
+    for old_index in removed.drain(..) {
+        match added_iter.next_if(|&new_index| new[new_index].0 == old[old_index].0) {
+            Some(new_index) => diffs.push(NodeDiff::Modified {
+                old_index,
+                new_index,
+                old: old[old_index].1.clone(),
+                new: new[new_index].1.clone(),
+            }),
+            None => diffs.push(NodeDiff::Removed {
+                index: old_index,
+                text: old[old_index].1.clone(),
+            }),
+        }
+    }
+    for index in added_iter {
+        diffs.push(NodeDiff::Added {
+            index,
+            text: new[index].1.clone(),
+        });
+    }
+}
+
+/// The links of a document, grouped by where they point.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkReport {
+    /// Relative links and wikilinks, which stay on the same site.
+    pub internal: Vec<Link>,
+    /// `http://`, `https://` and protocol-relative `//host` links.
+    pub external: Vec<Link>,
+    /// Same-document `#fragment` links.
+    pub anchors: Vec<Link>,
+    /// Links with any other scheme, such as `mailto:` or `tel:`.
+    pub other: Vec<Link>,
+}
+
+/// Groups every link from `extract_links` by its destination,
+/// keeping document order within each group.
+pub fn classify_links<'a>(root: &'a AstNode<'a>) -> LinkReport {
+    let mut report = LinkReport::default();
+    for link in extract_links(root) {
+        let url = link.url.to_ascii_lowercase();
+        let bucket = if link.wikilink {
+            &mut report.internal
+        } else if url.starts_with("http://") || url.starts_with("https://") || url.starts_with("//")
+        {
+            &mut report.external
+        } else if url.starts_with('#') {
+            &mut report.anchors
+        } else if has_scheme(&url) {
+            &mut report.other
+        } else {
+            &mut report.internal
+        };
+        bucket.push(link);
+    }
+    report
+}
+
+/// Returns whether a URL starts with a scheme such as `https:`.
+pub(crate) fn has_scheme(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Returns the slug `extract_headings` gives to the first heading
+/// with this text, before any `-1`, `-2`, ... suffix is added.
+pub(crate) fn base_slug(text: &str) -> String {
+    Anchorizer::new().anchorize(text.to_string())
+}
+
+/// Concatenates the inline text under a node, dropping formatting.
+fn inline_text<'a>(node: &'a AstNode<'a>) -> String {
+    let mut text = Vec::new();
+    collect_text(node, &mut text);
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Shortens `text` to at most `max` characters, cutting between words
+/// and ending in an ellipsis. Whitespace runs collapse to single spaces.
+fn truncate_words(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut out = String::new();
+    let mut len = 0;
+    for word in text.split_whitespace() {
+        let word_len = word.chars().count();
+        let sep = usize::from(len > 0);
+        // Leave room for the ellipsis.
+        if len + sep + word_len + 1 > max {
+            break;
+        }
+        if sep == 1 {
+            out.push(' ');
+        }
+        out.push_str(word);
+        len += sep + word_len;
+    }
+    out.push('…');
+    out
+}
+
+fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Arena, Flavor, extract_ast_from_str};
+
+    #[test]
+    fn test_word_count() {
+        let arena = Arena::new();
+        let md = "# Main Title\n\
+                  \n\
+                  Some `inline code` here.\n\
+                  \n\
+                  ## Sub heading\n\
+                  \n\
+                  - first item\n\
+                  - second item\n\
+                  \n\
+                  ```rust\n\
+                  fn main() {}\n\
+                  ```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        // 2 + 4 + 2 + 4 + 3
+        assert_eq!(word_count(root), 15);
+    }
+
+    #[test]
+    fn test_word_count_skips_html_and_alt_text() {
+        let arena = Arena::new();
+        let md = "<div>ignored words</div>\n\nOne ![alt text here](a.png) two";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(word_count(root), 2);
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let arena = Arena::new();
+        let md = "word ".repeat(400);
+        let root = extract_ast_from_str(&md, Flavor::CommonMark, &arena);
+
+        assert_eq!(reading_time(root, 200), Duration::from_secs(120));
+        assert_eq!(default_reading_time(root), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_reading_time_rounds_up() {
+        let arena = Arena::new();
+        let root = extract_ast_from_str("Just a few words.", Flavor::CommonMark, &arena);
+        assert_eq!(reading_time(root, 200), Duration::from_secs(60));
+
+        let empty = extract_ast_from_str("", Flavor::CommonMark, &arena);
+        assert_eq!(reading_time(empty, 200), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_extract_headings() {
+        let arena = Arena::new();
+        let md = "# Getting *Started*\n\n## What's New?\n\ntext\n\n### Sub-section 2\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let headings = extract_headings(root);
+        assert_eq!(
+            headings,
+            vec![
+                Heading {
+                    level: 1,
+                    text: "Getting Started".to_string(),
+                    slug: "getting-started".to_string(),
+                    start_line: 1,
+                    start_col: 1,
+                    end_line: 1,
+                    end_col: 19,
+                },
+                Heading {
+                    level: 2,
+                    text: "What's New?".to_string(),
+                    slug: "whats-new".to_string(),
+                    start_line: 3,
+                    start_col: 1,
+                    end_line: 3,
+                    end_col: 14,
+                },
+                Heading {
+                    level: 3,
+                    text: "Sub-section 2".to_string(),
+                    slug: "sub-section-2".to_string(),
+                    start_line: 7,
+                    start_col: 1,
+                    end_line: 7,
+                    end_col: 17,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_headings_dedupes_slugs() {
+        let arena = Arena::new();
+        let md = "## Intro\n\n## Intro\n\n## Intro\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let slugs: Vec<_> = extract_headings(root).into_iter().map(|h| h.slug).collect();
+        assert_eq!(slugs, vec!["intro", "intro-1", "intro-2"]);
+    }
+
+    #[test]
+    fn test_document_title() {
+        let arena = Arena::new();
+        let title = |md: &str| document_title(extract_ast_from_str(md, Flavor::CommonMark, &arena));
+
+        assert_eq!(
+            title("Intro text.\n\n# The Title\n\n# Another\n"),
+            Some("The Title".to_string())
+        );
+        assert_eq!(title("## Only a subheading\n"), None);
+        assert_eq!(
+            title("# *Hello* `big` [world](https://w.example)\n"),
+            Some("Hello big world".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let arena = Arena::new();
+        let md = "An [inline](https://a.example \"A title\") link, \
+                  a [reference][ref] link and https://c.example too.\n\
+                  \n\
+                  [ref]: https://b.example\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+
+        assert_eq!(
+            extract_links(root),
+            vec![
+                Link {
+                    url: "https://a.example".to_string(),
+                    text: "inline".to_string(),
+                    title: Some("A title".to_string()),
+                    wikilink: false,
+                    start_line: 1,
+                    start_col: 4,
+                    end_line: 1,
+                    end_col: 40,
+                },
+                Link {
+                    url: "https://b.example".to_string(),
+                    text: "reference".to_string(),
+                    title: None,
+                    wikilink: false,
+                    start_line: 1,
+                    start_col: 50,
+                    end_line: 1,
+                    end_col: 65,
+                },
+                Link {
+                    url: "https://c.example".to_string(),
+                    text: "https://c.example".to_string(),
+                    title: None,
+                    wikilink: false,
+                    start_line: 1,
+                    start_col: 76,
+                    end_line: 1,
+                    end_col: 92,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_link_definitions() {
+        let md = "See [the docs][Docs] and [home].\n\
+                  \n\
+                  [docs]: https://docs.example \"Manual\"\n\
+                  [Home]: /index.html\n\
+                  [home]: /ignored.html\n\
+                  [^1]: A footnote, not a link.\n\
+                  \n\
+                  ```\n\
+                  [fake]: /in-a-code-block\n\
+                  ```\n";
+        let definitions = extract_link_definitions(md);
+
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(
+            definitions["docs"],
+            (
+                "https://docs.example".to_string(),
+                Some("Manual".to_string())
+            )
+        );
+        // The first of two definitions wins, as it does when resolving.
+        assert_eq!(definitions["home"], ("/index.html".to_string(), None));
+    }
+
+    #[test]
+    fn test_unused_link_definitions() {
+        let md = "A [used][Used Label] link and a [shortcut].\n\
+                  \n\
+                  [used   label]: https://used.example\n\
+                  [shortcut]: https://shortcut.example\n\
+                  [stale]: https://stale.example\n";
+
+        assert_eq!(unused_link_definitions(md), vec!["stale"]);
+        assert!(unused_link_definitions("No definitions here.").is_empty());
+    }
+
+    #[test]
+    fn test_extract_images() {
+        let arena = Arena::new();
+        let md = "![A diagram](./img/diagram.png)\n\n\
+                  ![Logo](https://cdn.example/logo.svg \"Our logo\")\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            extract_images(root),
+            vec![
+                Image {
+                    url: "./img/diagram.png".to_string(),
+                    alt: "A diagram".to_string(),
+                    title: None,
+                    start_line: 1,
+                    start_col: 1,
+                    end_line: 1,
+                    end_col: 31,
+                },
+                Image {
+                    url: "https://cdn.example/logo.svg".to_string(),
+                    alt: "Logo".to_string(),
+                    title: Some("Our logo".to_string()),
+                    start_line: 3,
+                    start_col: 1,
+                    end_line: 3,
+                    end_col: 48,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_code_blocks_by_language() {
+        let arena = Arena::new();
+        let md = "```Rust\nfn main() {}\n```\n\
+                  \n\
+                  ```python\nprint()\n```\n\
+                  \n\
+                  ```\nno language\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let rust = extract_code_blocks_by_language(root, "rust");
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].literal, "fn main() {}\n");
+        assert_eq!(extract_code_blocks_by_language(root, "PYTHON").len(), 1);
+        assert!(extract_code_blocks_by_language(root, "").is_empty());
+    }
+
+    #[test]
+    fn test_code_language_histogram() {
+        let arena = Arena::new();
+        let md = "```rust\na\n```\n\n```Rust\nb\n```\n\n```python\nc\n```\n\n\
+                  ```\nd\n```\n\n    indented\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let histogram = code_language_histogram(root);
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram["rust"], 2);
+        assert_eq!(histogram["python"], 1);
+        assert_eq!(histogram[""], 1);
+    }
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let arena = Arena::new();
+        let md = "```rust ignore\nfn main() {}\n```\n\
+                  \n\
+                  Some text.\n\
+                  \n    \
+                  indented code\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            extract_code_blocks(root),
+            vec![
+                CodeBlock {
+                    language: Some("rust".to_string()),
+                    literal: "fn main() {}\n".to_string(),
+                    fenced: true,
+                    info: CodeBlockInfo {
+                        language: Some("rust".to_string()),
+                        flags: vec!["ignore".to_string()],
+                        attributes: HashMap::new(),
+                    },
+                },
+                CodeBlock {
+                    language: None,
+                    literal: "indented code\n".to_string(),
+                    fenced: false,
+                    info: CodeBlockInfo::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_footnotes() {
+        let arena = Arena::new();
+        let md = "Resolved[^note] and orphan[^missing].\n\n[^note]: The *definition*.\n";
+        let root = extract_ast_from_str(md, Flavor::MultiMarkdown, &arena);
+
+        assert_eq!(
+            extract_footnotes(root),
+            vec![Footnote {
+                label: "note".to_string(),
+                text: "The definition.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_extract_tasks() {
+        let arena = Arena::new();
+        let md = "- [x] Write *docs*\n- [ ] Ship it\n  - [X] Nested\n- plain item\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+
+        assert_eq!(
+            extract_tasks(root),
+            vec![
+                TaskItem {
+                    checked: true,
+                    text: "Write docs".to_string(),
+                },
+                TaskItem {
+                    checked: false,
+                    text: "Ship it".to_string(),
+                },
+                TaskItem {
+                    checked: true,
+                    text: "Nested".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_tables() {
+        let arena = Arena::new();
+        let md = "| Name | Score |\n|------|------:|\n| *Ada* | 10 |\n| Bob |\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+
+        assert_eq!(
+            extract_tables(root),
+            vec![Table {
+                headers: vec!["Name".to_string(), "Score".to_string()],
+                rows: vec![
+                    vec!["Ada".to_string(), "10".to_string()],
+                    vec!["Bob".to_string(), String::new()],
+                ],
+                alignments: vec![Alignment::None, Alignment::Right],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_node_stats() {
+        let arena = Arena::new();
+        let md = "# Title\n\nOne [link](a.md).\n\n## Part\n\nTwo.\n\n```\ncode\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let stats = node_stats(root);
+        assert_eq!(stats["document"], 1);
+        assert_eq!(stats["heading"], 2);
+        assert_eq!(stats["paragraph"], 2);
+        assert_eq!(stats["link"], 1);
+        assert_eq!(stats["code_block"], 1);
+        assert!(!stats.contains_key("table"));
+    }
+
+    #[test]
+    fn test_sourcepos() {
+        let arena = Arena::new();
+        let md = "Intro.\n\n## Setup\n\nSee\n  [the guide](guide.md).\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let heading = &extract_headings(root)[0];
+        assert_eq!((heading.start_line, heading.start_col), (3, 1));
+        assert_eq!((heading.end_line, heading.end_col), (3, 8));
+
+        let link = &extract_links(root)[0];
+        assert_eq!((link.start_line, link.start_col), (6, 3));
+        assert_eq!((link.end_line, link.end_col), (6, 23));
+    }
+
+    #[test]
+    fn test_extract_definitions() {
+        let arena = Arena::new();
+        let mut options = Flavor::CommonMark.to_options();
+        options.extension.description_lists = true;
+        let md =
+            "Arena\n\n: A bump allocator.\n\n: Where nodes *live*.\n\nNode\n: An AST element.\n";
+        let root = extract_ast_from_str(md, Flavor::Custom(options), &arena);
+
+        assert_eq!(
+            extract_definitions(root),
+            vec![
+                (
+                    "Arena".to_string(),
+                    vec![
+                        "A bump allocator.".to_string(),
+                        "Where nodes live.".to_string()
+                    ]
+                ),
+                ("Node".to_string(), vec!["An AST element.".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_blockquotes() {
+        let arena = Arena::new();
+        let md = "> Single quote.\n\nText.\n\n> Outer\n>\n> > Inner\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            extract_blockquotes(root),
+            vec![
+                Blockquote {
+                    depth: 1,
+                    text: "Single quote.".to_string(),
+                },
+                Blockquote {
+                    depth: 1,
+                    text: "Outer\nInner".to_string(),
+                },
+                Blockquote {
+                    depth: 2,
+                    text: "Inner".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_alerts() {
+        let md =
+            "> [!WARNING]\n> Mind the *gap*.\n\n> Just a quote.\n\n> [!TODO]\n> Not an alert.\n";
+        let expected = vec![Alert {
+            kind: AlertKind::Warning,
+            content_html: "<p>Mind the <em>gap</em>.</p>\n".to_string(),
+        }];
+
+        let arena = Arena::new();
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+        assert_eq!(extract_alerts(root), expected);
+
+        // comrak's own alert nodes give the same result.
+        let mut options = Flavor::GitHub.to_options();
+        options.extension.alerts = true;
+        let root = extract_ast_from_str(md, Flavor::Custom(options), &arena);
+        assert_eq!(extract_alerts(root), expected);
+    }
+
+    #[test]
+    fn test_split_into_sections() {
+        let arena = Arena::new();
+        let md =
+            "Intro.\n\n# Title\n\nLead.\n\n## One\n\nFirst.\n\n> ## Quoted\n\n## Two\n\nSecond.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let sections = split_into_sections(root);
+        let titles: Vec<_> = sections
+            .iter()
+            .map(|section| {
+                section
+                    .heading
+                    .as_ref()
+                    .map(|heading| heading.text.as_str())
+            })
+            .collect();
+        assert_eq!(titles, [None, Some("Title"), Some("One"), Some("Two")]);
+
+        let types = |section: &Section| -> Vec<&str> {
+            section.content.iter().map(OwnedNode::node_type).collect()
+        };
+        assert_eq!(types(&sections[0]), ["paragraph"]);
+        assert_eq!(
+            types(&sections[1]),
+            [
+                "paragraph",
+                "heading",
+                "paragraph",
+                "block_quote",
+                "heading",
+                "paragraph"
+            ]
+        );
+        assert_eq!(types(&sections[2]), ["paragraph", "block_quote"]);
+        assert_eq!(types(&sections[3]), ["paragraph"]);
+
+        let arena = Arena::new();
+        let second = from_owned_tree(&sections[3].content[0], &arena);
+        assert_eq!(to_plain_text(second), "Second.");
+        assert_eq!(sections[3].heading.as_ref().unwrap().slug, "two");
+    }
+
+    #[test]
+    fn test_summarize() {
+        let arena = Arena::new();
+        let md = "- not this\n\n# My Post\n\n![cover](cover.png)\n\n\
+                  The first paragraph of the post, which goes on for a while.\n\n\
+                  ## More\n\n# Second title\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let summary = summarize(root);
+        assert_eq!(summary.title.as_deref(), Some("My Post"));
+        assert_eq!(summary.image.as_deref(), Some("cover.png"));
+        assert_eq!(summary.word_count, 19);
+        assert_eq!(
+            summary.description.as_deref(),
+            Some("The first paragraph of the post, which goes on for a while.")
+        );
+
+        let md = "# Title\n\nThe first paragraph of the post, which goes on.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+        assert_eq!(
+            summarize_with_max_length(root, 25).description.as_deref(),
+            Some("The first paragraph of…")
+        );
+        assert_eq!(
+            summarize(root).description.as_deref(),
+            Some("The first paragraph of the post, which goes on.")
+        );
+    }
+
+    #[test]
+    fn test_code_block_info() {
+        let info = CodeBlockInfo::parse("rust,no_run,ignore");
+        assert_eq!(info.language.as_deref(), Some("rust"));
+        assert_eq!(info.flags, vec!["no_run", "ignore"]);
+        assert!(info.attributes.is_empty());
+
+        let info =
+            CodeBlockInfo::parse("python {.line-numbers #example startFrom=\"5\" title='a b'}");
+        assert_eq!(info.language.as_deref(), Some("python"));
+        assert_eq!(info.flags, vec!["line-numbers"]);
+        assert_eq!(
+            info.attributes,
+            HashMap::from([
+                ("id".to_string(), "example".to_string()),
+                ("startFrom".to_string(), "5".to_string()),
+                ("title".to_string(), "a b".to_string()),
+            ])
+        );
+
+        let info = CodeBlockInfo::parse("{.haskell .numberLines}");
+        assert_eq!(info.language.as_deref(), Some("haskell"));
+        assert_eq!(info.flags, vec!["numberLines"]);
+
+        assert_eq!(CodeBlockInfo::parse(""), CodeBlockInfo::default());
+    }
+
+    #[test]
+    fn test_diff_documents() {
+        let arena = Arena::new();
+        let old = "# Title\n\nThe quick brown\nfox jumps.\n\nSecond paragraph.\n\nThird.\n";
+        let new = "# Title\n\nThe quick\nbrown fox jumps.\n\nSecond changed paragraph.\n\nThird.\n";
+        let old = extract_ast_from_str(old, Flavor::CommonMark, &arena);
+        let new = extract_ast_from_str(new, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            diff_documents(old, new),
+            vec![NodeDiff::Modified {
+                old_index: 2,
+                new_index: 2,
+                old: "Second paragraph.".to_string(),
+                new: "Second changed paragraph.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_documents_added_and_removed() {
+        let arena = Arena::new();
+        let old = extract_ast_from_str("One.\n\n- item\n", Flavor::CommonMark, &arena);
+        let new = extract_ast_from_str("## Intro\n\nOne.\n", Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            diff_documents(old, new),
+            vec![
+                NodeDiff::Added {
+                    index: 0,
+                    text: "Intro".to_string(),
+                },
+                NodeDiff::Removed {
+                    index: 1,
+                    text: "item".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_links() {
+        let arena = Arena::new();
+        let md = "[site](https://a.example), [guide](docs/guide.md), [top](#top), \
+                  [mail](mailto:me@a.example) and [cdn](//cdn.example/x)\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let urls = |links: Vec<Link>| links.into_iter().map(|link| link.url).collect::<Vec<_>>();
+        let report = classify_links(root);
+        assert_eq!(
+            urls(report.external),
+            vec!["https://a.example", "//cdn.example/x"]
+        );
+        assert_eq!(urls(report.internal), vec!["docs/guide.md"]);
+        assert_eq!(urls(report.anchors), vec!["#top"]);
+        assert_eq!(urls(report.other), vec!["mailto:me@a.example"]);
+    }
+}
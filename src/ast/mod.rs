@@ -0,0 +1,292 @@
+/**
+ * ============================================================================
+ * AST Traversal Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Generic helpers for walking the comrak AST, so analysis
+ * code doesn't need to re-implement tree traversal.
+ * ============================================================================
+*/
+use comrak::Arena;
+use comrak::nodes::{Ast, AstNode, NodeValue, Sourcepos};
+use std::ops::Range;
+
+/// Visits nodes during a depth-first walk of the AST.
+///
+/// Implementors override `visit` and inspect `node.data.borrow().value`
+/// to pick out the nodes they care about.
+pub trait Visitor {
+    /// Called once for every node, parents before their children.
+    fn visit<'a>(&mut self, _node: &'a AstNode<'a>) {}
+}
+
+/// Walks the tree rooted at `root` in pre-order, calling the visitor
+/// on every node including the root itself.
+pub fn walk<'a, V: Visitor>(root: &'a AstNode<'a>, visitor: &mut V) {
+    visitor.visit(root);
+    for child in root.children() {
+        walk(child, visitor);
+    }
+}
+
+/// Iterates over the tree rooted at `root` in depth-first pre-order,
+/// starting with the root itself.
+///
+/// The iterator is lazy and keeps no buffer of pending nodes,
+/// so it is cheap even for very large documents.
+pub fn iter_nodes<'a>(root: &'a AstNode<'a>) -> impl Iterator<Item = &'a AstNode<'a>> {
+    root.descendants()
+}
+
+/// Returns a stable, snake_case name for the kind of a node,
+/// such as `"heading"`, `"paragraph"` or `"code_block"`.
+///
+/// The names follow comrak's XML output and are safe to persist.
+pub fn node_type_name(value: &NodeValue) -> &'static str {
+    match value {
+        NodeValue::Document => "document",
+        NodeValue::FrontMatter(_) => "frontmatter",
+        NodeValue::BlockQuote => "block_quote",
+        NodeValue::List(_) => "list",
+        NodeValue::Item(_) => "item",
+        NodeValue::DescriptionList => "description_list",
+        NodeValue::DescriptionItem(_) => "description_item",
+        NodeValue::DescriptionTerm => "description_term",
+        NodeValue::DescriptionDetails => "description_details",
+        NodeValue::CodeBlock(_) => "code_block",
+        NodeValue::HtmlBlock(_) => "html_block",
+        NodeValue::Paragraph => "paragraph",
+        NodeValue::Heading(_) => "heading",
+        NodeValue::ThematicBreak => "thematic_break",
+        NodeValue::FootnoteDefinition(_) => "footnote_definition",
+        NodeValue::Table(_) => "table",
+        NodeValue::TableRow(_) => "table_row",
+        NodeValue::TableCell => "table_cell",
+        NodeValue::Text(_) => "text",
+        NodeValue::TaskItem(_) => "taskitem",
+        NodeValue::SoftBreak => "softbreak",
+        NodeValue::LineBreak => "linebreak",
+        NodeValue::Code(_) => "code",
+        NodeValue::HtmlInline(_) => "html_inline",
+        NodeValue::Raw(_) => "raw",
+        NodeValue::Emph => "emph",
+        NodeValue::Strong => "strong",
+        NodeValue::Strikethrough => "strikethrough",
+        NodeValue::Superscript => "superscript",
+        NodeValue::Link(_) => "link",
+        NodeValue::Image(_) => "image",
+        NodeValue::FootnoteReference(_) => "footnote_reference",
+        NodeValue::Math(_) => "math",
+        NodeValue::MultilineBlockQuote(_) => "multiline_block_quote",
+        NodeValue::Escaped => "escaped",
+        NodeValue::WikiLink(_) => "wikilink",
+        NodeValue::Underline => "underline",
+        NodeValue::Subscript => "subscript",
+        NodeValue::SpoileredText => "spoiler",
+        NodeValue::EscapedTag(_) => "escaped_tag",
+        NodeValue::Alert(_) => "alert",
+    }
+}
+
+/// Returns the byte range in `source` that `node` was parsed from.
+///
+/// comrak records a start and an inclusive end position for every node,
+/// as 1-based lines and byte columns, whatever the `sourcepos` render
+/// option says; that option only adds them to HTML. `source` must be the
+/// exact text the node was parsed from. Returns `None` for nodes without
+/// a position, such as ones created by transforms, or when the position
+/// doesn't fall on character boundaries of `source`.
+pub fn node_byte_range<'a>(node: &'a AstNode<'a>, source: &str) -> Option<Range<usize>> {
+    let pos = node.data.borrow().sourcepos;
+    if pos.start.line == 0 || pos.start.column == 0 {
+        return None;
+    }
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let start = line_starts.get(pos.start.line - 1)? + pos.start.column - 1;
+    let end = line_starts.get(pos.end.line.checked_sub(1)?)? + pos.end.column;
+    let end = end.min(source.len());
+
+    let on_boundaries = source.is_char_boundary(start) && source.is_char_boundary(end);
+    (start <= end && on_boundaries).then_some(start..end)
+}
+
+/// An owned copy of an AST node and its children, free of any arena.
+///
+/// The node kind and its payload are kept as comrak's own `NodeValue`,
+/// so every kind is mirrored exactly. Owned trees can be returned from
+/// functions, stored and sent across threads without keeping an arena
+/// alive. The cost is a deep copy: converting clones every string in
+/// the tree, and each node with children adds a `Vec` allocation on top
+/// of what the arena holds. Prefer the borrowed AST for one-off passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedNode {
+    pub value: NodeValue,
+    pub sourcepos: Sourcepos,
+    pub children: Vec<OwnedNode>,
+}
+
+impl OwnedNode {
+    /// Returns the stable name of this node's kind; see `node_type_name`.
+    pub fn node_type(&self) -> &'static str {
+        node_type_name(&self.value)
+    }
+}
+
+/// Copies the tree rooted at `root` into an `OwnedNode`.
+pub fn to_owned_tree<'a>(root: &'a AstNode<'a>) -> OwnedNode {
+    let ast = root.data.borrow();
+    OwnedNode {
+        value: ast.value.clone(),
+        sourcepos: ast.sourcepos,
+        children: root.children().map(to_owned_tree).collect(),
+    }
+}
+
+/// Allocates a copy of an owned tree in `arena`, the inverse of
+/// `to_owned_tree`.
+pub fn from_owned_tree<'a>(node: &OwnedNode, arena: &'a Arena<AstNode<'a>>) -> &'a AstNode<'a> {
+    let mut ast = Ast::new(node.value.clone(), node.sourcepos.start);
+    ast.sourcepos = node.sourcepos;
+    let copy = arena.alloc(AstNode::from(ast));
+    for child in &node.children {
+        copy.append(from_owned_tree(child, arena));
+    }
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Arena, Flavor, extract_ast_from_str};
+    use comrak::nodes::NodeValue;
+    use std::mem::discriminant;
+
+    /// Counts nodes of the same kind as `target`.
+    struct KindCounter {
+        target: NodeValue,
+        count: usize,
+    }
+
+    impl Visitor for KindCounter {
+        fn visit<'a>(&mut self, node: &'a AstNode<'a>) {
+            if discriminant(&node.data.borrow().value) == discriminant(&self.target) {
+                self.count += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_counts_nodes() {
+        let arena = Arena::new();
+        let md = "# Title\n\nFirst paragraph.\n\n- item with text\n\nLast paragraph.";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let mut counter = KindCounter {
+            target: NodeValue::Paragraph,
+            count: 0,
+        };
+        walk(root, &mut counter);
+
+        // Two top-level paragraphs plus the one inside the list item.
+        assert_eq!(counter.count, 3);
+    }
+
+    #[test]
+    fn test_iter_nodes_order() {
+        let arena = Arena::new();
+        let md = "- a\n  - b\n- c\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let kinds: Vec<String> = iter_nodes(root)
+            .map(|node| match node.data.borrow().value {
+                NodeValue::Document => "document".to_string(),
+                NodeValue::List(_) => "list".to_string(),
+                NodeValue::Item(_) => "item".to_string(),
+                NodeValue::Paragraph => "paragraph".to_string(),
+                NodeValue::Text(ref text) => text.clone(),
+                ref other => format!("{:?}", other),
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "document",
+                "list",
+                "item",
+                "paragraph",
+                "a",
+                "list",
+                "item",
+                "paragraph",
+                "b",
+                "item",
+                "paragraph",
+                "c",
+            ]
+        );
+    }
+
+    /// Renders an owned tree as `type(child, ...)` for compact comparisons.
+    fn shape(node: &OwnedNode) -> String {
+        if node.children.is_empty() {
+            return node.node_type().to_string();
+        }
+        let children: Vec<_> = node.children.iter().map(shape).collect();
+        format!("{}({})", node.node_type(), children.join(", "))
+    }
+
+    #[test]
+    fn test_to_owned_tree() {
+        let owned = {
+            let arena = Arena::new();
+            let md = "# Title\n\n- one\n- *two*\n";
+            to_owned_tree(extract_ast_from_str(md, Flavor::CommonMark, &arena))
+        };
+
+        assert_eq!(
+            shape(&owned),
+            "document(heading(text), list(item(paragraph(text)), item(paragraph(emph(text)))))"
+        );
+        let heading = &owned.children[0];
+        assert!(matches!(heading.value, NodeValue::Heading(ref h) if h.level == 1));
+        assert_eq!(heading.sourcepos.start.line, 1);
+        assert_eq!(
+            heading.children[0].value,
+            NodeValue::Text("Title".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_owned_tree_round_trip() {
+        let md = "# Title\n\n> quoted *text*\n";
+        let arena = Arena::new();
+        let owned = to_owned_tree(extract_ast_from_str(md, Flavor::CommonMark, &arena));
+
+        let copy_arena = Arena::new();
+        let copy = from_owned_tree(&owned, &copy_arena);
+        assert_eq!(to_owned_tree(copy), owned);
+    }
+
+    #[test]
+    fn test_node_byte_range() {
+        let arena = Arena::new();
+        let md = "Café *au lait*\n## Menu\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let heading = root.children().nth(1).unwrap();
+        let range = node_byte_range(heading, md).unwrap();
+        // "é" takes two bytes, so line 2 starts at byte 16, not 15.
+        assert_eq!(range, 16..23);
+        assert_eq!(&md[range], "## Menu");
+
+        let emph = root.first_child().unwrap().children().nth(1).unwrap();
+        assert_eq!(&md[node_byte_range(emph, md).unwrap()], "*au lait*");
+
+        let detached = arena.alloc(AstNode::from(NodeValue::Paragraph));
+        assert_eq!(node_byte_range(detached, md), None);
+    }
+}
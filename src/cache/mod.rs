@@ -0,0 +1,243 @@
+/**
+ * ============================================================================
+ * Parse Cache Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Cache parsed documents across calls so unchanged files
+ * are not re-read and re-parsed in watch mode or rebuilds.
+ * ============================================================================
+*/
+use crate::error::MkforgeError;
+use crate::parser::{Flavor, OwnedDocument, parse_owned};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+struct CacheEntry {
+    modified: SystemTime,
+    flavor: Flavor,
+    document: OwnedDocument,
+}
+
+/// A cache of parsed documents keyed by canonicalized path.
+///
+/// A file is only re-parsed when its last-modified time changes
+/// or it is requested with a different flavor.
+///
+/// Flavors are compared with `==`, so swapping only a callback in
+/// `Flavor::Custom` options doesn't re-parse.
+#[derive(Default)]
+pub struct ParseCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+impl ParseCache {
+    pub fn new() -> Self {
+        ParseCache::default()
+    }
+
+    /// Returns the cached document for `path`, parsing it first if it
+    /// isn't cached yet or has been modified since it was cached.
+    ///
+    /// If the file can no longer be read, e.g. because it was deleted,
+    /// its entry is evicted and the read error is returned.
+    pub fn get_or_parse(
+        &mut self,
+        path: impl AsRef<Path>,
+        flavor: Flavor,
+    ) -> Result<&OwnedDocument, MkforgeError> {
+        let path = path.as_ref();
+        let (key, modified) = match file_state(path) {
+            Ok(state) => state,
+            Err(source) => {
+                self.evict(path);
+                return Err(MkforgeError::ReadFile {
+                    path: path.display().to_string(),
+                    source,
+                });
+            }
+        };
+
+        let fresh = self
+            .entries
+            .get(&key)
+            .is_some_and(|entry| entry.modified == modified && entry.flavor == flavor);
+        if !fresh {
+            let document = match parse_owned(path, flavor.clone()) {
+                Ok(document) => document,
+                Err(err) => {
+                    self.entries.remove(&key);
+                    return Err(err);
+                }
+            };
+            let entry = CacheEntry {
+                modified,
+                flavor,
+                document,
+            };
+            self.entries.insert(key.clone(), entry);
+        }
+
+        Ok(&self.entries[&key].document)
+    }
+
+    /// Returns the number of cached documents.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no documents are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes the entry for a path that may no longer exist.
+    /// The file itself can't be canonicalized, so resolve its directory.
+    fn evict(&mut self, path: &Path) {
+        if let Ok(key) = canonical_parent_key(path) {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+/// Returns the cache key and last-modified time of a file.
+fn file_state(path: &Path) -> io::Result<(PathBuf, SystemTime)> {
+    let key = fs::canonicalize(path)?;
+    let modified = fs::metadata(&key)?.modified()?;
+    Ok((key, modified))
+}
+
+fn canonical_parent_key(path: &Path) -> io::Result<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    Ok(fs::canonicalize(parent)?.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mkforge-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("doc.md");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_get_or_parse_reuses_unchanged_file() {
+        let path = temp_file("reuse", "# First");
+        let mut cache = ParseCache::new();
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(
+            cache
+                .get_or_parse(&path, Flavor::CommonMark)
+                .unwrap()
+                .source,
+            "# First"
+        );
+
+        // Change the content but restore the mtime: the cache can't tell.
+        fs::write(&path, "# Second").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(
+            cache
+                .get_or_parse(&path, Flavor::CommonMark)
+                .unwrap()
+                .source,
+            "# First"
+        );
+        assert_eq!(cache.len(), 1);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_get_or_parse_reuses_custom_flavor() {
+        use crate::parser::ParseConfig;
+
+        let path = temp_file("custom", "# First");
+        let mut cache = ParseCache::new();
+        let flavor = || ParseConfig::builder("").footnotes(true).build().as_flavor();
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        cache.get_or_parse(&path, flavor()).unwrap();
+
+        // As above, a re-parse would pick up the new content.
+        fs::write(&path, "# Second").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(
+            cache.get_or_parse(&path, flavor()).unwrap().source,
+            "# First"
+        );
+
+        // Different options are a different flavor.
+        let other = ParseConfig::builder("")
+            .footnotes(false)
+            .build()
+            .as_flavor();
+        assert_eq!(cache.get_or_parse(&path, other).unwrap().source, "# Second");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_get_or_parse_touch_invalidates() {
+        let path = temp_file("touch", "# First");
+        let mut cache = ParseCache::new();
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        cache.get_or_parse(&path, Flavor::CommonMark).unwrap();
+
+        fs::write(&path, "# Second").unwrap();
+        let touched = modified + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+        let doc = cache.get_or_parse(&path, Flavor::CommonMark).unwrap();
+        assert_eq!(doc.headings[0].text, "Second");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_get_or_parse_deleted_file_evicts() {
+        let path = temp_file("deleted", "# First");
+        let mut cache = ParseCache::new();
+        cache.get_or_parse(&path, Flavor::CommonMark).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        fs::remove_file(&path).unwrap();
+        let err = cache.get_or_parse(&path, Flavor::CommonMark).unwrap_err();
+        assert!(matches!(err, MkforgeError::ReadFile { .. }));
+        assert!(cache.is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1 +1,202 @@
+/**
+ * ============================================================================
+ * CLI Commands Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Define the mkforge subcommands and run them on top of
+ * the library's parsing and extraction functions.
+ * ============================================================================
+*/
+use clap::{Args, Parser, Subcommand};
+use comrak::nodes::AstNode;
+use mkforge::analysis::{extract_headings, extract_links};
+use mkforge::ast::iter_nodes;
+use mkforge::error::MkforgeError;
+use mkforge::json::to_json;
+use mkforge::parser::{Arena, Flavor, ParseConfig, extract_ast, extract_ast_from_reader};
+use mkforge::renderer::{TocFormat, render_toc};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// How long a file must stay quiet before a change is acted on.
+///
+/// Editors often write a file in several steps, so events are coalesced
+/// until none have arrived for this long.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Parse and inspect Markdown files.
+#[derive(Debug, Parser)]
+#[command(name = "mkforge", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(flatten)]
+    Run(Operation),
+    /// Re-run an operation every time its file changes.
+    Watch {
+        #[command(subcommand)]
+        operation: Operation,
+    },
+}
+
+/// A one-shot operation on a single file.
+#[derive(Debug, Subcommand)]
+pub enum Operation {
+    /// Parse a file and print its node count.
+    Parse {
+        #[command(flatten)]
+        input: Input,
+        /// Print the whole AST as JSON instead.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a Markdown table of contents for a file.
+    Toc {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Print every link destination in a file, one per line.
+    Links {
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+/// The file a subcommand reads and how to parse it.
+#[derive(Debug, Clone, Args)]
+pub struct Input {
+    /// The Markdown file to read, or `-` to read standard input.
+    pub file: PathBuf,
+    /// The Markdown flavor, e.g. `commonmark`, `gfm` or `mmd`.
+    #[arg(long, short, default_value = "commonmark")]
+    pub flavor: String,
+}
+
+/// Runs a subcommand, returning what it prints on success.
+pub fn run(operation: &Operation) -> Result<String, MkforgeError> {
+    let arena = Arena::new();
+    match operation {
+        Operation::Parse { input, json } => {
+            let root = input.parse(&arena)?;
+            if *json {
+                // The AST mirror only holds strings and numbers, so it always serializes.
+                let json = serde_json::to_string_pretty(&to_json(root)).unwrap();
+                Ok(format!("{}\n", json))
+            } else {
+                Ok(format!("{} nodes\n", iter_nodes(root).count()))
+            }
+        }
+        Operation::Toc { input } => {
+            let root = input.parse(&arena)?;
+            Ok(render_toc(&extract_headings(root), TocFormat::Markdown))
+        }
+        Operation::Links { input } => {
+            let root = input.parse(&arena)?;
+            Ok(extract_links(root)
+                .into_iter()
+                .map(|link| link.url + "\n")
+                .collect())
+        }
+    }
+}
+
+/// Runs `operation` once, then again every time its file changes.
+///
+/// The file's parent directory is watched rather than the file itself,
+/// so editors that save by replacing the file are still picked up.
+/// Each re-run is announced on `out` with a UTC timestamp. Failed runs
+/// are reported on stderr without stopping the watch, since the file can
+/// briefly be missing mid-save. Only returns if the watch itself fails,
+/// or immediately for `-`, since standard input can't be watched.
+pub fn watch(operation: &Operation, out: &mut impl Write) -> Result<(), MkforgeError> {
+    let input = operation.input();
+    if input.is_stdin() {
+        let err = io::Error::new(io::ErrorKind::InvalidInput, "cannot watch standard input");
+        return Err(err.into());
+    }
+
+    let file = &input.file;
+    let dir = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(io::Error::other)?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(io::Error::other)?;
+
+    print_run(operation, out)?;
+    for event in &rx {
+        let touches_file = event.is_ok_and(|event| {
+            matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == file.file_name())
+        });
+        if !touches_file {
+            continue;
+        }
+
+        while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+        writeln!(out, "[{}] re-parsed {}", utc_timestamp(), file.display())?;
+        print_run(operation, out)?;
+    }
+
+    Ok(())
+}
+
+fn print_run(operation: &Operation, out: &mut impl Write) -> io::Result<()> {
+    match run(operation) {
+        Ok(output) => write!(out, "{}", output)?,
+        Err(e) => eprintln!("error: {}", e),
+    }
+    out.flush()
+}
+
+/// Formats the current UTC time of day as `HH:MM:SS`.
+fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+impl Operation {
+    fn input(&self) -> &Input {
+        match self {
+            Operation::Parse { input, .. }
+            | Operation::Toc { input }
+            | Operation::Links { input } => input,
+        }
+    }
+}
+
+impl Input {
+    fn is_stdin(&self) -> bool {
+        self.file.as_os_str() == "-"
+    }
+
+    fn parse<'a>(&self, arena: &'a Arena<AstNode<'a>>) -> Result<&'a AstNode<'a>, MkforgeError> {
+        let flavor: Flavor = self.flavor.parse()?;
+        if self.is_stdin() {
+            extract_ast_from_reader(&mut io::stdin().lock(), flavor, arena)
+        } else {
+            extract_ast(&ParseConfig::new(&self.file, flavor), arena)
+        }
+    }
+}
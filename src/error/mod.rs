@@ -0,0 +1,130 @@
+/**
+ * ============================================================================
+ * Error Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Define the error type shared by every fallible operation in the crate.
+ * ============================================================================
+*/
+use std::fmt;
+use std::io;
+
+/// Errors produced while configuring, reading or parsing Markdown.
+#[derive(Debug)]
+pub enum MkforgeError {
+    /// Reading the Markdown source failed.
+    Io(io::Error),
+    /// Reading the Markdown file at `path` failed.
+    ReadFile { path: String, source: io::Error },
+    /// The Markdown source was not valid UTF-8.
+    NotUtf8,
+    /// A string did not name a known Markdown flavor.
+    UnknownFlavor(String),
+    /// A directory symlink points back into one of its own ancestors.
+    SymlinkLoop { path: String },
+    /// The frontmatter block could not be deserialized.
+    Frontmatter(String),
+    /// A file includes itself, directly or through other includes.
+    IncludeCycle { path: String },
+    /// Includes are nested deeper than the configured maximum.
+    IncludeTooDeep { path: String, max_depth: usize },
+    /// A configuration file could not be deserialized.
+    Config(String),
+    /// The Markdown source is longer than the configured maximum.
+    InputTooLarge { max_bytes: usize },
+}
+
+impl fmt::Display for MkforgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MkforgeError::Io(err) => write!(f, "{}", err),
+            MkforgeError::ReadFile { path, source } => {
+                write!(f, "failed to read `{}`: {}", path, source)
+            }
+            MkforgeError::NotUtf8 => write!(f, "input is not valid UTF-8"),
+            MkforgeError::UnknownFlavor(name) => write!(f, "unknown Markdown flavor `{}`", name),
+            MkforgeError::SymlinkLoop { path } => {
+                write!(f, "symlink loop detected at `{}`", path)
+            }
+            MkforgeError::Frontmatter(message) => write!(f, "invalid frontmatter: {}", message),
+            MkforgeError::IncludeCycle { path } => write!(f, "circular include of `{}`", path),
+            MkforgeError::IncludeTooDeep { path, max_depth } => write!(
+                f,
+                "including `{}` exceeds the maximum include depth of {}",
+                path, max_depth
+            ),
+            MkforgeError::Config(message) => write!(f, "invalid config: {}", message),
+            MkforgeError::InputTooLarge { max_bytes } => {
+                write!(f, "input exceeds the maximum size of {} bytes", max_bytes)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MkforgeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MkforgeError::Io(err) => Some(err),
+            MkforgeError::ReadFile { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MkforgeError {
+    fn from(err: io::Error) -> Self {
+        MkforgeError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            MkforgeError::NotUtf8.to_string(),
+            "input is not valid UTF-8"
+        );
+        assert_eq!(
+            MkforgeError::UnknownFlavor("foo".to_string()).to_string(),
+            "unknown Markdown flavor `foo`"
+        );
+        assert_eq!(
+            MkforgeError::IncludeTooDeep {
+                path: "a.md".to_string(),
+                max_depth: 3,
+            }
+            .to_string(),
+            "including `a.md` exceeds the maximum include depth of 3"
+        );
+        assert_eq!(
+            MkforgeError::Config("unknown field `foo`".to_string()).to_string(),
+            "invalid config: unknown field `foo`"
+        );
+        assert_eq!(
+            MkforgeError::InputTooLarge { max_bytes: 10 }.to_string(),
+            "input exceeds the maximum size of 10 bytes"
+        );
+    }
+
+    #[test]
+    fn test_display_read_file() {
+        let err = MkforgeError::ReadFile {
+            path: "docs/intro.md".to_string(),
+            source: io::Error::new(io::ErrorKind::NotFound, "No such file or directory"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "failed to read `docs/intro.md`: No such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let err: MkforgeError = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(err, MkforgeError::Io(_)));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
@@ -0,0 +1,201 @@
+/**
+ * ============================================================================
+ * Frontmatter Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Detect and separate the metadata block at the top of
+ * a Markdown document from the content that follows it.
+ * ============================================================================
+*/
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use crate::error::MkforgeError;
+
+/// Strips a trailing `\n` or `\r\n` from a line.
+fn trim_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Delimiter of YAML frontmatter blocks.
+pub const YAML_DELIMITER: &str = "---";
+
+/// Delimiter of TOML frontmatter blocks, as used by Hugo.
+pub const TOML_DELIMITER: &str = "+++";
+
+/// Splits a leading `---` delimited frontmatter block from the content.
+///
+/// Returns the raw text between the delimiters and the Markdown after
+/// the closing delimiter. If the document has no frontmatter, or the
+/// block is never closed, returns `None` and the original text.
+/// Both `\n` and `\r\n` line endings are recognized.
+pub fn split_frontmatter(content: &str) -> (Option<String>, &str) {
+    split_frontmatter_with_delim(content, YAML_DELIMITER)
+}
+
+/// Splits a leading frontmatter block fenced by `delim` lines.
+///
+/// Works like `split_frontmatter`. The block is only recognized when the
+/// very first line is the delimiter, so a delimiter appearing later
+/// in the body is never mistaken for frontmatter.
+pub fn split_frontmatter_with_delim<'c>(
+    content: &'c str,
+    delim: &str,
+) -> (Option<String>, &'c str) {
+    let mut lines = content.split_inclusive('\n');
+    let start = match lines.next() {
+        Some(first) if trim_line_ending(first) == delim => first.len(),
+        _ => return (None, content),
+    };
+
+    let mut offset = start;
+    for line in lines {
+        if trim_line_ending(line) == delim {
+            let frontmatter = content[start..offset].to_string();
+            return (Some(frontmatter), &content[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Parses the YAML frontmatter of a document into `T`.
+///
+/// Returns `Ok(None)` when the document has no frontmatter block.
+/// Malformed YAML surfaces as `MkforgeError::Frontmatter`.
+#[cfg(feature = "yaml")]
+pub fn parse_frontmatter<T: serde::de::DeserializeOwned>(
+    content: &str,
+) -> Result<Option<T>, MkforgeError> {
+    match split_frontmatter(content) {
+        (Some(frontmatter), _) => serde_yaml::from_str(&frontmatter)
+            .map(Some)
+            .map_err(|err| MkforgeError::Frontmatter(err.to_string())),
+        (None, _) => Ok(None),
+    }
+}
+
+/// Parses the `+++` delimited TOML frontmatter of a document into `T`.
+///
+/// Returns `Ok(None)` when the document has no frontmatter block.
+/// Malformed TOML surfaces as `MkforgeError::Frontmatter`.
+#[cfg(feature = "toml")]
+pub fn parse_toml_frontmatter<T: serde::de::DeserializeOwned>(
+    content: &str,
+) -> Result<Option<T>, MkforgeError> {
+    match split_frontmatter_with_delim(content, TOML_DELIMITER) {
+        (Some(frontmatter), _) => toml::from_str(&frontmatter)
+            .map(Some)
+            .map_err(|err| MkforgeError::Frontmatter(err.to_string())),
+        (None, _) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let content = "---\ntitle: Hello\ntags: [a, b]\n---\n# Body\n";
+        assert_eq!(
+            split_frontmatter(content),
+            (Some("title: Hello\ntags: [a, b]\n".to_string()), "# Body\n")
+        );
+    }
+
+    #[test]
+    fn test_split_frontmatter_missing() {
+        let content = "# Body\n\n---\n\nMore text.\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_frontmatter_unterminated() {
+        let content = "---\ntitle: Hello\n# Body\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_frontmatter_windows_line_endings() {
+        let content = "---\r\ntitle: Hello\r\n---\r\n# Body\r\n";
+        assert_eq!(
+            split_frontmatter(content),
+            (Some("title: Hello\r\n".to_string()), "# Body\r\n")
+        );
+    }
+
+    #[test]
+    fn test_split_frontmatter_with_delim() {
+        let content = "+++\ntitle = \"Hello\"\n+++\n# Body\n";
+        assert_eq!(
+            split_frontmatter_with_delim(content, TOML_DELIMITER),
+            (Some("title = \"Hello\"\n".to_string()), "# Body\n")
+        );
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn test_split_frontmatter_with_delim_mid_document() {
+        let content = "# Body\n\n+++\nnot = \"frontmatter\"\n+++\n";
+        assert_eq!(
+            split_frontmatter_with_delim(content, TOML_DELIMITER),
+            (None, content)
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_parse_frontmatter() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Post {
+            title: String,
+            date: String,
+        }
+
+        let content = "---\ntitle: Hello World\ndate: 2025-01-31\n---\n# Body\n";
+        let post: Option<Post> = parse_frontmatter(content).unwrap();
+        assert_eq!(
+            post,
+            Some(Post {
+                title: "Hello World".to_string(),
+                date: "2025-01-31".to_string(),
+            })
+        );
+
+        let none: Option<Post> = parse_frontmatter("# Body\n").unwrap();
+        assert_eq!(none, None);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_parse_frontmatter_malformed() {
+        let content = "---\ntitle: [unclosed\n---\n# Body\n";
+        let result: Result<Option<serde_yaml::Value>, _> = parse_frontmatter(content);
+        assert!(matches!(result, Err(MkforgeError::Frontmatter(_))));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_parse_toml_frontmatter() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Post {
+            title: String,
+            draft: bool,
+        }
+
+        let content = "+++\ntitle = \"Hello World\"\ndraft = true\n+++\n# Body\n";
+        let post: Option<Post> = parse_toml_frontmatter(content).unwrap();
+        assert_eq!(
+            post,
+            Some(Post {
+                title: "Hello World".to_string(),
+                draft: true,
+            })
+        );
+
+        let body_only = "# Body\n\n+++\ntitle = \"Nope\"\n+++\n";
+        let none: Option<Post> = parse_toml_frontmatter(body_only).unwrap();
+        assert_eq!(none, None);
+    }
+}
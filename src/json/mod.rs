@@ -0,0 +1,409 @@
+/**
+ * ============================================================================
+ * JSON Serialization Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Mirror the comrak AST in a serde-friendly form so parse
+ * output can be consumed by tooling in other languages.
+ * ============================================================================
+*/
+use crate::ast::node_type_name;
+use crate::lint::{Diagnostic, Severity};
+#[cfg(feature = "pandoc")]
+use crate::parser::Flavor;
+#[cfg(feature = "pandoc")]
+use crate::renderer::to_markdown;
+use comrak::nodes::{AstNode, NodeValue};
+#[cfg(feature = "pandoc")]
+use comrak::nodes::{ListDelimType, ListType};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "pandoc")]
+use serde_json::Value;
+use serde_json::json;
+use std::path::Path;
+
+/// A serializable copy of an AST node and its children.
+///
+/// `type` is always present and uses the names from `node_type_name`.
+/// Payload fields are only emitted for the node kinds that carry them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstJson {
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Level of a heading, from 1 to 6.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
+    /// Destination of a link or image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Title of a link or image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Info string of a code block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<String>,
+    /// Literal contents of code, HTML and math nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub literal: Option<String>,
+    /// Contents of a text node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<AstJson>,
+}
+
+/// Converts the tree rooted at `root` into its serializable mirror.
+pub fn to_json<'a>(root: &'a AstNode<'a>) -> AstJson {
+    let value = &root.data.borrow().value;
+    let mut json = AstJson {
+        node_type: node_type_name(value).to_string(),
+        children: root.children().map(to_json).collect(),
+        ..Default::default()
+    };
+
+    match value {
+        NodeValue::Heading(heading) => json.level = Some(heading.level),
+        NodeValue::Link(link) | NodeValue::Image(link) => {
+            json.url = Some(link.url.clone());
+            json.title = Some(link.title.clone());
+        }
+        NodeValue::WikiLink(link) => json.url = Some(link.url.clone()),
+        NodeValue::CodeBlock(block) => {
+            json.info = Some(block.info.clone());
+            json.literal = Some(block.literal.clone());
+        }
+        NodeValue::Code(code) => json.literal = Some(code.literal.clone()),
+        NodeValue::HtmlBlock(html) => json.literal = Some(html.literal.clone()),
+        NodeValue::HtmlInline(html) | NodeValue::Raw(html) => json.literal = Some(html.clone()),
+        NodeValue::Math(math) => json.literal = Some(math.literal.clone()),
+        NodeValue::Text(text) => json.text = Some(text.clone()),
+        _ => {}
+    }
+
+    json
+}
+
+/// Converts lint diagnostics for the file at `path` into a SARIF 2.1.0
+/// log, the format GitHub code scanning and other CI tools import.
+///
+/// The log has a single run whose rules are the distinct
+/// `Diagnostic::rule` ids, in order of first use. Each result points at
+/// `path` as given, with a region for its line; diagnostics without a
+/// line point at the whole file.
+pub fn diagnostics_to_sarif(path: &Path, diagnostics: &[Diagnostic]) -> String {
+    let uri = path.to_string_lossy().replace('\\', "/");
+    let mut rules: Vec<&str> = Vec::new();
+    for diagnostic in diagnostics {
+        if !rules.contains(&diagnostic.rule) {
+            rules.push(diagnostic.rule);
+        }
+    }
+
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut location = json!({ "artifactLocation": { "uri": uri } });
+            if let Some(line) = diagnostic.line {
+                location["region"] = json!({ "startLine": line });
+            }
+            json!({
+                "ruleId": diagnostic.rule,
+                "level": match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Note => "note",
+                },
+                "message": { "text": diagnostic.message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mkforge",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    });
+    // A `Value` always serializes.
+    serde_json::to_string_pretty(&sarif).unwrap()
+}
+
+/// The version of the Pandoc AST that `to_pandoc_json` emits.
+#[cfg(feature = "pandoc")]
+pub const PANDOC_API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Converts the tree rooted at `root` into Pandoc's JSON AST, as read by
+/// `pandoc -f json` and passed to Pandoc filters.
+///
+/// Paragraphs, headings, code blocks, blockquotes, lists and thematic
+/// breaks map to their Pandoc blocks, with paragraphs in tight lists
+/// becoming `Plain` like Pandoc's own Markdown reader does. Task list
+/// items start with a ☐ or ☒ box. Raw HTML becomes `RawBlock` and
+/// `RawInline` in the `html` format. Anything else Pandoc has no direct
+/// equivalent for here, such as tables, footnotes and alerts, falls back
+/// to a `markdown` raw block or inline holding its CommonMark rendering.
+/// Front matter is dropped and `meta` is always empty.
+#[cfg(feature = "pandoc")]
+pub fn to_pandoc_json<'a>(root: &'a AstNode<'a>) -> String {
+    let pandoc = json!({
+        "pandoc-api-version": PANDOC_API_VERSION,
+        "meta": {},
+        "blocks": pandoc_blocks(root, false),
+    });
+    pandoc.to_string()
+}
+
+#[cfg(feature = "pandoc")]
+fn pandoc_blocks<'a>(node: &'a AstNode<'a>, tight: bool) -> Vec<Value> {
+    node.children()
+        .filter_map(|child| pandoc_block(child, tight))
+        .collect()
+}
+
+#[cfg(feature = "pandoc")]
+fn pandoc_block<'a>(node: &'a AstNode<'a>, tight: bool) -> Option<Value> {
+    let block = match &node.data.borrow().value {
+        NodeValue::FrontMatter(_) => return None,
+        NodeValue::Paragraph => {
+            let tag = if tight { "Plain" } else { "Para" };
+            json!({ "t": tag, "c": pandoc_inlines(node) })
+        }
+        NodeValue::Heading(heading) => json!({
+            "t": "Header",
+            "c": [heading.level, pandoc_attr(&[]), pandoc_inlines(node)],
+        }),
+        NodeValue::CodeBlock(block) => {
+            let classes: Vec<&str> = block.info.split_whitespace().take(1).collect();
+            let literal = block.literal.strip_suffix('\n').unwrap_or(&block.literal);
+            json!({ "t": "CodeBlock", "c": [pandoc_attr(&classes), literal] })
+        }
+        NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+            json!({ "t": "BlockQuote", "c": pandoc_blocks(node, false) })
+        }
+        NodeValue::List(list) => {
+            let items: Vec<_> = node
+                .children()
+                .map(|item| pandoc_item(item, list.tight))
+                .collect();
+            match list.list_type {
+                ListType::Bullet => json!({ "t": "BulletList", "c": items }),
+                ListType::Ordered => {
+                    let delimiter = match list.delimiter {
+                        ListDelimType::Period => "Period",
+                        ListDelimType::Paren => "OneParen",
+                    };
+                    let attributes = json!([list.start, { "t": "Decimal" }, { "t": delimiter }]);
+                    json!({ "t": "OrderedList", "c": [attributes, items] })
+                }
+            }
+        }
+        NodeValue::ThematicBreak => json!({ "t": "HorizontalRule" }),
+        NodeValue::HtmlBlock(html) => json!({ "t": "RawBlock", "c": ["html", html.literal] }),
+        _ => json!({ "t": "RawBlock", "c": ["markdown", to_markdown(node, Flavor::GitHub)] }),
+    };
+    Some(block)
+}
+
+/// Converts a list item into its blocks, prefixing task items with a box.
+#[cfg(feature = "pandoc")]
+fn pandoc_item<'a>(item: &'a AstNode<'a>, tight: bool) -> Vec<Value> {
+    let mut blocks = pandoc_blocks(item, tight);
+    if let NodeValue::TaskItem(mark) = item.data.borrow().value
+        && let Some(first) = blocks.first_mut()
+        && (first["t"] == "Plain" || first["t"] == "Para")
+        && let Some(inlines) = first["c"].as_array_mut()
+    {
+        let checkbox = if mark.is_some() { "☒" } else { "☐" };
+        inlines.splice(
+            0..0,
+            [
+                json!({ "t": "Str", "c": checkbox }),
+                json!({ "t": "Space" }),
+            ],
+        );
+    }
+    blocks
+}
+
+#[cfg(feature = "pandoc")]
+fn pandoc_inlines<'a>(node: &'a AstNode<'a>) -> Vec<Value> {
+    let mut inlines = Vec::new();
+    for child in node.children() {
+        push_pandoc_inline(child, &mut inlines);
+    }
+    inlines
+}
+
+#[cfg(feature = "pandoc")]
+fn push_pandoc_inline<'a>(node: &'a AstNode<'a>, inlines: &mut Vec<Value>) {
+    let inline = match &node.data.borrow().value {
+        NodeValue::Text(text) => {
+            // Pandoc keeps words and the spaces between them apart.
+            let mut words = text.split(' ');
+            if let Some(word) = words.next().filter(|word| !word.is_empty()) {
+                inlines.push(json!({ "t": "Str", "c": word }));
+            }
+            for word in words {
+                inlines.push(json!({ "t": "Space" }));
+                if !word.is_empty() {
+                    inlines.push(json!({ "t": "Str", "c": word }));
+                }
+            }
+            return;
+        }
+        NodeValue::Escaped => {
+            inlines.extend(pandoc_inlines(node));
+            return;
+        }
+        NodeValue::SoftBreak => json!({ "t": "SoftBreak" }),
+        NodeValue::LineBreak => json!({ "t": "LineBreak" }),
+        NodeValue::Code(code) => json!({ "t": "Code", "c": [pandoc_attr(&[]), code.literal] }),
+        NodeValue::Emph => json!({ "t": "Emph", "c": pandoc_inlines(node) }),
+        NodeValue::Strong => json!({ "t": "Strong", "c": pandoc_inlines(node) }),
+        NodeValue::Strikethrough => json!({ "t": "Strikeout", "c": pandoc_inlines(node) }),
+        NodeValue::Superscript => json!({ "t": "Superscript", "c": pandoc_inlines(node) }),
+        NodeValue::Subscript => json!({ "t": "Subscript", "c": pandoc_inlines(node) }),
+        NodeValue::Underline => json!({ "t": "Underline", "c": pandoc_inlines(node) }),
+        NodeValue::Link(link) => json!({
+            "t": "Link",
+            "c": [pandoc_attr(&[]), pandoc_inlines(node), [link.url, link.title]],
+        }),
+        NodeValue::Image(link) => json!({
+            "t": "Image",
+            "c": [pandoc_attr(&[]), pandoc_inlines(node), [link.url, link.title]],
+        }),
+        // Pandoc's own wikilinks extension marks them with this title.
+        NodeValue::WikiLink(link) => json!({
+            "t": "Link",
+            "c": [pandoc_attr(&[]), pandoc_inlines(node), [link.url, "wikilink"]],
+        }),
+        NodeValue::HtmlInline(html) => json!({ "t": "RawInline", "c": ["html", html] }),
+        NodeValue::Math(math) => {
+            let kind = if math.display_math {
+                "DisplayMath"
+            } else {
+                "InlineMath"
+            };
+            json!({ "t": "Math", "c": [{ "t": kind }, math.literal] })
+        }
+        _ => {
+            let markdown = to_markdown(node, Flavor::GitHub);
+            json!({ "t": "RawInline", "c": ["markdown", markdown.trim_end()] })
+        }
+    };
+    inlines.push(inline);
+}
+
+/// Builds a Pandoc `Attr`: an identifier, classes and key-value pairs.
+#[cfg(feature = "pandoc")]
+fn pandoc_attr(classes: &[&str]) -> Value {
+    json!(["", classes, []])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Arena, Flavor, extract_ast_from_str};
+
+    #[test]
+    fn test_to_json_round_trip() {
+        let arena = Arena::new();
+        let md = "# Hi\n\nSee [docs](https://docs.example).\n\n```rust\nfn main() {}\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let json = to_json(root);
+        let encoded = serde_json::to_string(&json).unwrap();
+        let decoded: AstJson = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, json);
+
+        let value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(value["type"], "document");
+        assert_eq!(value["children"][0]["type"], "heading");
+        assert_eq!(value["children"][0]["level"], 1);
+        assert_eq!(
+            value["children"][1]["children"][1]["url"],
+            "https://docs.example"
+        );
+        assert_eq!(value["children"][2]["literal"], "fn main() {}\n");
+    }
+
+    #[test]
+    fn test_diagnostics_to_sarif() {
+        let diagnostic = |rule, line| Diagnostic {
+            rule,
+            severity: Severity::Warning,
+            message: "problem".to_string(),
+            line,
+        };
+        let diagnostics = [
+            diagnostic("heading-hierarchy", Some(3)),
+            diagnostic("missing-alt", None),
+            diagnostic("heading-hierarchy", Some(9)),
+        ];
+
+        let sarif = diagnostics_to_sarif(Path::new("docs/guide.md"), &diagnostics);
+        let value: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+        assert_eq!(value["version"], "2.1.0");
+
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "missing-alt");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["ruleId"], "heading-hierarchy");
+        assert_eq!(results[0]["level"], "warning");
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/guide.md");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert!(
+            results[1]["locations"][0]["physicalLocation"]
+                .get("region")
+                .is_none()
+        );
+    }
+
+    #[cfg(feature = "pandoc")]
+    #[test]
+    fn test_to_pandoc_json() {
+        let arena = Arena::new();
+        let md = "# Hi\n\nSome *em* text.\n\n- [x] done\n- b\n\n```rust\nfn main() {}\n```\n\n\
+                  | a |\n| - |\n| 1 |\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+
+        let value: serde_json::Value = serde_json::from_str(&to_pandoc_json(root)).unwrap();
+        assert_eq!(value["pandoc-api-version"], json!([1, 23, 1]));
+        assert_eq!(value["meta"], json!({}));
+
+        let blocks = value["blocks"].as_array().unwrap();
+        assert_eq!(blocks.len(), 5);
+        assert_eq!(
+            blocks[0],
+            json!({ "t": "Header", "c": [1, ["", [], []], [{ "t": "Str", "c": "Hi" }]] })
+        );
+        assert_eq!(blocks[1]["t"], "Para");
+        assert_eq!(blocks[1]["c"][2]["t"], "Emph");
+        assert_eq!(blocks[1]["c"].as_array().unwrap().len(), 5);
+        assert_eq!(blocks[2]["t"], "BulletList");
+        assert_eq!(blocks[2]["c"][0][0]["t"], "Plain");
+        assert_eq!(
+            blocks[2]["c"][0][0]["c"][0],
+            json!({ "t": "Str", "c": "☒" })
+        );
+        assert_eq!(
+            blocks[3],
+            json!({ "t": "CodeBlock", "c": [["", ["rust"], []], "fn main() {}"] })
+        );
+        assert_eq!(blocks[4]["t"], "RawBlock");
+        assert_eq!(blocks[4]["c"][0], "markdown");
+    }
+}
@@ -0,0 +1,19 @@
+/**
+ * ============================================================================
+ * mkforge
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Library entry point exposing the Markdown parsing modules.
+ * ============================================================================
+*/
+pub mod analysis;
+pub mod ast;
+pub mod cache;
+pub mod error;
+pub mod frontmatter;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod lint;
+pub mod parser;
+pub mod renderer;
+pub mod transform;
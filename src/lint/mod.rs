@@ -0,0 +1,821 @@
+/**
+ * ============================================================================
+ * Markdown Lint Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Check a parsed Markdown document for common quality
+ * problems such as broken links and navigation.
+ * ============================================================================
+*/
+#[cfg(feature = "http-check")]
+use crate::analysis::Link;
+use crate::analysis::{base_slug, extract_headings, extract_images, extract_links, has_scheme};
+use comrak::nodes::AstNode;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// A `#fragment` link with no heading to land on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenAnchor {
+    /// The fragment without its leading `#`.
+    pub fragment: String,
+    /// The visible text of the link.
+    pub text: String,
+}
+
+/// Reports links to `#fragment`s that don't match any heading slug.
+///
+/// Slugs come from `extract_headings`, so they match the anchors comrak
+/// renders. Only same-document links starting with `#` are checked;
+/// fragments into other files (`other.md#x`) are ignored.
+pub fn check_internal_anchors<'a>(root: &'a AstNode<'a>) -> Vec<BrokenAnchor> {
+    let slugs: HashSet<String> = extract_headings(root)
+        .into_iter()
+        .map(|heading| heading.slug)
+        .collect();
+
+    extract_links(root)
+        .into_iter()
+        .filter_map(|link| {
+            let fragment = link.url.strip_prefix('#')?;
+            // A bare `#` links to the top of the page.
+            if fragment.is_empty() || slugs.contains(fragment) {
+                return None;
+            }
+            Some(BrokenAnchor {
+                fragment: fragment.to_string(),
+                text: link.text,
+            })
+        })
+        .collect()
+}
+
+/// A relative link whose target file doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The link destination as written.
+    pub url: String,
+    /// The visible text of the link.
+    pub text: String,
+    /// The path the destination resolved to.
+    pub resolved: PathBuf,
+}
+
+/// Reports relative links whose target doesn't exist on disk.
+///
+/// Destinations are resolved against the directory containing
+/// `doc_path`, after dropping any `#fragment` or `?query` suffix and
+/// percent-decoding the rest, so `my%20file.md` finds `my file.md`.
+/// Links with a scheme (`https:`, `mailto:`, ...), same-document
+/// `#anchors`, site-absolute `/paths` and wikilinks, which name pages
+/// rather than files, are skipped.
+pub fn check_relative_links<'a>(doc_path: &Path, root: &'a AstNode<'a>) -> Vec<BrokenLink> {
+    let base = doc_path.parent().unwrap_or(Path::new(""));
+
+    extract_links(root)
+        .into_iter()
+        .filter_map(|link| {
+            if link.wikilink || has_scheme(&link.url) || link.url.starts_with(['#', '/']) {
+                return None;
+            }
+            let target = link.url.split(['#', '?']).next().unwrap_or_default();
+            if target.is_empty() {
+                return None;
+            }
+
+            let resolved = base.join(percent_decode(target));
+            if resolved.exists() {
+                return None;
+            }
+            Some(BrokenLink {
+                url: link.url,
+                text: link.text,
+                resolved,
+            })
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes in a URL path. Malformed escapes are kept as
+/// written, and bytes that don't form UTF-8 become U+FFFD.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// A problem with the nesting of a document's headings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadingWarning {
+    /// A heading more than one level deeper than the heading before it,
+    /// such as an h3 directly after an h1.
+    SkippedLevel {
+        text: String,
+        from: u8,
+        to: u8,
+        line: usize,
+    },
+    /// An h1 after the document's first h1.
+    MultipleTopLevel { text: String, line: usize },
+}
+
+/// Reports every heading that skips a level relative to the heading
+/// before it. Moving back up any number of levels is always fine, as is
+/// starting the document below h1.
+///
+/// Use `lint_multiple_top_level` to also require a single h1.
+pub fn lint_heading_hierarchy<'a>(root: &'a AstNode<'a>) -> Vec<HeadingWarning> {
+    let headings = extract_headings(root);
+    headings
+        .windows(2)
+        .filter(|pair| pair[1].level > pair[0].level + 1)
+        .map(|pair| HeadingWarning::SkippedLevel {
+            text: pair[1].text.clone(),
+            from: pair[0].level,
+            to: pair[1].level,
+            line: pair[1].start_line,
+        })
+        .collect()
+}
+
+/// Reports every h1 after the first, for style guides that reserve
+/// h1 for the document title.
+pub fn lint_multiple_top_level<'a>(root: &'a AstNode<'a>) -> Vec<HeadingWarning> {
+    extract_headings(root)
+        .into_iter()
+        .filter(|heading| heading.level == 1)
+        .skip(1)
+        .map(|heading| HeadingWarning::MultipleTopLevel {
+            text: heading.text,
+            line: heading.start_line,
+        })
+        .collect()
+}
+
+/// Headings that would share an anchor if slugs weren't deduplicated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateHeading {
+    /// The slug the headings have in common.
+    pub slug: String,
+    /// The text of every colliding heading, in document order.
+    pub texts: Vec<String>,
+    /// The 1-based source line of each heading in `texts`.
+    pub lines: Vec<usize>,
+}
+
+/// Reports every heading slug that occurs more than once.
+///
+/// comrak keeps anchors unique by suffixing repeats with `-1`, `-2`, ...,
+/// so links written against the plain slug silently land on the first
+/// match. Slugs are computed exactly like `extract_headings` does, so
+/// headings that differ only in case or punctuation collide too.
+/// Reports are ordered by each slug's first occurrence.
+pub fn lint_duplicate_headings<'a>(root: &'a AstNode<'a>) -> Vec<DuplicateHeading> {
+    let mut slugs: Vec<DuplicateHeading> = Vec::new();
+    for heading in extract_headings(root) {
+        let slug = base_slug(&heading.text);
+        match slugs.iter_mut().find(|duplicate| duplicate.slug == slug) {
+            Some(duplicate) => {
+                duplicate.texts.push(heading.text);
+                duplicate.lines.push(heading.start_line);
+            }
+            None => slugs.push(DuplicateHeading {
+                slug,
+                texts: vec![heading.text],
+                lines: vec![heading.start_line],
+            }),
+        }
+    }
+
+    slugs.retain(|duplicate| duplicate.texts.len() > 1);
+    slugs
+}
+
+/// An image without alt text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAlt {
+    pub url: String,
+    /// The 1-based source line of the image.
+    pub line: usize,
+}
+
+/// Reports every image whose alt text is empty or only whitespace.
+///
+/// Purely decorative images are meant to have empty alt text, but
+/// Markdown gives no way to mark an image as decorative, so every image
+/// without alt text is flagged and callers have to filter out any that
+/// are intentional.
+pub fn lint_missing_alt<'a>(root: &'a AstNode<'a>) -> Vec<MissingAlt> {
+    extract_images(root)
+        .into_iter()
+        .filter(|image| image.alt.trim().is_empty())
+        .map(|image| MissingAlt {
+            url: image.url,
+            line: image.start_line,
+        })
+        .collect()
+}
+
+/// A source line longer than the configured maximum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineWarning {
+    /// The 1-based line number.
+    pub line: usize,
+    /// The length of the line in characters.
+    pub length: usize,
+}
+
+/// Reports every source line longer than `max` characters.
+///
+/// This works on the raw source rather than the AST, since parsing
+/// loses the original line breaks. Lines inside fenced code blocks and
+/// GFM tables are skipped, because they can't be wrapped without
+/// changing their meaning.
+pub fn lint_line_length(content: &str, max: usize) -> Vec<LineWarning> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut warnings = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut in_table = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((marker, len)) = fence {
+            if fence_marker(line).is_some_and(|(c, n)| c == marker && n >= len) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(line) {
+            fence = Some(marker);
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            in_table = false;
+        } else if !in_table {
+            // A table starts at a header row directly followed by a delimiter row.
+            in_table =
+                line.contains('|') && lines.get(i + 1).is_some_and(|next| is_delimiter_row(next));
+        }
+        if in_table {
+            continue;
+        }
+
+        let length = line.chars().count();
+        if length > max {
+            warnings.push(LineWarning {
+                line: i + 1,
+                length,
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Returns the fence character and run length if `line` opens or closes
+/// a fenced code block.
+pub(crate) fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let rest = &line[indent..];
+    let marker = rest.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = rest.len() - rest.trim_start_matches(marker).len();
+    (indent <= 3 && len >= 3).then_some((marker, len))
+}
+
+/// Returns whether `line` is a GFM table delimiter row such as `|---|:-:|`.
+fn is_delimiter_row(line: &str) -> bool {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').all(|cell| {
+        let cell = cell.trim();
+        let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+        !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+    })
+}
+
+/// How serious a `Diagnostic` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    /// A style suggestion.
+    Note,
+}
+
+/// A problem reported by `lint`, in the same shape whichever check found it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The id of the check that reported it, such as `"missing-alt"`.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// The 1-based source line, if the problem has one.
+    pub line: Option<usize>,
+}
+
+/// The checks `lint` runs. Every check is enabled by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    /// Run `lint_heading_hierarchy`.
+    pub heading_hierarchy: bool,
+    /// Run `lint_multiple_top_level`.
+    pub multiple_top_level: bool,
+    /// Run `lint_duplicate_headings`.
+    pub duplicate_headings: bool,
+    /// Run `lint_missing_alt`.
+    pub missing_alt: bool,
+    /// Run `lint_line_length` with this maximum, or not at all if `None`.
+    pub max_line_length: Option<usize>,
+}
+
+/// Default maximum line length used by `LintConfig::default`.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 80;
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            heading_hierarchy: true,
+            multiple_top_level: true,
+            duplicate_headings: true,
+            missing_alt: true,
+            max_line_length: Some(DEFAULT_MAX_LINE_LENGTH),
+        }
+    }
+}
+
+/// Runs every check enabled in `config` and merges their reports.
+///
+/// `source` must be the text `root` was parsed from, since the line
+/// length check works on raw lines. Diagnostics are sorted by line, with
+/// ones that have no line last; diagnostics on the same line keep the
+/// order of the checks. A duplicated heading slug is reported at every
+/// heading after the first one using it. Missing alt text is an error,
+/// long lines are notes and everything else is a warning.
+pub fn lint<'a>(root: &'a AstNode<'a>, source: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |rule, severity, message, line| {
+        diagnostics.push(Diagnostic {
+            rule,
+            severity,
+            message,
+            line: Some(line),
+        })
+    };
+
+    let mut heading_warnings = Vec::new();
+    if config.heading_hierarchy {
+        heading_warnings.extend(lint_heading_hierarchy(root));
+    }
+    if config.multiple_top_level {
+        heading_warnings.extend(lint_multiple_top_level(root));
+    }
+    for warning in heading_warnings {
+        match warning {
+            HeadingWarning::SkippedLevel {
+                text,
+                from,
+                to,
+                line,
+            } => report(
+                "heading-hierarchy",
+                Severity::Warning,
+                format!("heading `{}` skips from h{} to h{}", text, from, to),
+                line,
+            ),
+            HeadingWarning::MultipleTopLevel { text, line } => report(
+                "multiple-top-level",
+                Severity::Warning,
+                format!("heading `{}` is another h1", text),
+                line,
+            ),
+        }
+    }
+    if config.duplicate_headings {
+        for duplicate in lint_duplicate_headings(root) {
+            for (text, line) in duplicate.texts.iter().zip(&duplicate.lines).skip(1) {
+                report(
+                    "duplicate-heading",
+                    Severity::Warning,
+                    format!("heading `{}` reuses the anchor `#{}`", text, duplicate.slug),
+                    *line,
+                );
+            }
+        }
+    }
+    if config.missing_alt {
+        for missing in lint_missing_alt(root) {
+            report(
+                "missing-alt",
+                Severity::Error,
+                format!("image `{}` has no alt text", missing.url),
+                missing.line,
+            );
+        }
+    }
+    if let Some(max) = config.max_line_length {
+        for warning in lint_line_length(source, max) {
+            report(
+                "line-length",
+                Severity::Note,
+                format!(
+                    "line is {} characters long, more than {}",
+                    warning.length, max
+                ),
+                warning.line,
+            );
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line.unwrap_or(usize::MAX));
+    diagnostics
+}
+
+/// Timeout for each request made by `check_external_links`.
+#[cfg(feature = "http-check")]
+pub const DEFAULT_LINK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The result of checking one external URL.
+#[cfg(feature = "http-check")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkOutcome {
+    /// The server answered with this HTTP status code.
+    Status(u16),
+    /// The request failed before a response arrived.
+    Error(String),
+}
+
+/// The health of an external URL.
+#[cfg(feature = "http-check")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkStatus {
+    pub url: String,
+    pub outcome: LinkOutcome,
+}
+
+#[cfg(feature = "http-check")]
+impl LinkStatus {
+    /// Returns `true` for 2xx and 3xx responses.
+    pub fn is_healthy(&self) -> bool {
+        matches!(self.outcome, LinkOutcome::Status(200..=399))
+    }
+}
+
+/// Checks every `http(s)://` link with at most `concurrency` requests in
+/// flight, using `DEFAULT_LINK_TIMEOUT` for each request.
+///
+/// See `check_external_links_with_timeout`.
+#[cfg(feature = "http-check")]
+pub async fn check_external_links(links: &[Link], concurrency: usize) -> Vec<LinkStatus> {
+    check_external_links_with_timeout(links, concurrency, DEFAULT_LINK_TIMEOUT).await
+}
+
+/// Checks every `http(s)://` link with at most `concurrency` requests in
+/// flight, giving up on a request after `timeout`.
+///
+/// Each URL is requested once, however often it appears, and results are
+/// returned in order of first appearance. A `HEAD` request is tried
+/// first, falling back to `GET` for servers that don't answer `HEAD`
+/// with a healthy status.
+#[cfg(feature = "http-check")]
+pub async fn check_external_links_with_timeout(
+    links: &[Link],
+    concurrency: usize,
+    timeout: std::time::Duration,
+) -> Vec<LinkStatus> {
+    use futures_util::stream::{self, StreamExt};
+
+    let mut seen = HashSet::new();
+    let urls: Vec<&str> = links
+        .iter()
+        .map(|link| link.url.as_str())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .filter(|url| seen.insert(*url))
+        .collect();
+
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(err) => {
+            let outcome = LinkOutcome::Error(err.to_string());
+            return urls
+                .into_iter()
+                .map(|url| LinkStatus {
+                    url: url.to_string(),
+                    outcome: outcome.clone(),
+                })
+                .collect();
+        }
+    };
+
+    stream::iter(urls)
+        .map(|url| {
+            let client = &client;
+            async move {
+                LinkStatus {
+                    url: url.to_string(),
+                    outcome: check_url(client, url).await,
+                }
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(feature = "http-check")]
+async fn check_url(client: &reqwest::Client, url: &str) -> LinkOutcome {
+    if let Ok(response) = client.head(url).send().await {
+        let status = response.status();
+        if status.is_success() || status.is_redirection() {
+            return LinkOutcome::Status(status.as_u16());
+        }
+    }
+
+    match client.get(url).send().await {
+        Ok(response) => LinkOutcome::Status(response.status().as_u16()),
+        Err(err) => LinkOutcome::Error(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Arena, Flavor, extract_ast_from_str};
+
+    #[test]
+    fn test_check_internal_anchors() {
+        let arena = Arena::new();
+        let md = "# Getting Started\n\n\
+                  See [setup](#getting-started), [missing](#nowhere) \
+                  and [elsewhere](other.md#nowhere).\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            check_internal_anchors(root),
+            vec![BrokenAnchor {
+                fragment: "nowhere".to_string(),
+                text: "missing".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_relative_links() {
+        let dir = std::env::temp_dir().join(format!("mkforge-links-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::create_dir_all(dir.join("guide")).unwrap();
+        std::fs::write(dir.join("guide/setup.md"), "# Setup").unwrap();
+        std::fs::write(dir.join("guide/my file.md"), "# Mine").unwrap();
+        let doc_path = dir.join("docs/index.md");
+
+        let arena = Arena::new();
+        let md = "[setup](../guide/setup.md#install), [gone](../guide/old.md), \
+                  [mine](../guide/my%20file.md?v=1), \
+                  [site](https://example.com), [mail](mailto:a@b.c), [top](#top)\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            check_relative_links(&doc_path, root),
+            vec![BrokenLink {
+                url: "../guide/old.md".to_string(),
+                text: "gone".to_string(),
+                resolved: dir.join("docs/../guide/old.md"),
+            }]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("my%20file.md"), "my file.md");
+        assert_eq!(percent_decode("caf%C3%A9%2fx"), "café/x");
+        assert_eq!(percent_decode("100%.md%2"), "100%.md%2");
+        assert_eq!(percent_decode("%zz%+1"), "%zz%+1");
+    }
+
+    /// Serves `/ok` with 200 and everything else with 404,
+    /// returning the server's base URL.
+    #[cfg(feature = "http-check")]
+    fn mock_server() -> String {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+                let status = if request_line.contains(" /ok ") {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        base
+    }
+
+    #[cfg(feature = "http-check")]
+    #[tokio::test]
+    async fn test_check_external_links() {
+        let base = mock_server();
+        let link = |path: &str| Link {
+            url: format!("{}{}", base, path),
+            text: path.to_string(),
+            ..Default::default()
+        };
+        let links = vec![
+            link("/ok"),
+            link("/missing"),
+            link("/ok"),
+            Link {
+                url: "../local.md".to_string(),
+                text: "local".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let statuses = check_external_links(&links, 2).await;
+        assert_eq!(
+            statuses,
+            vec![
+                LinkStatus {
+                    url: format!("{}/ok", base),
+                    outcome: LinkOutcome::Status(200),
+                },
+                LinkStatus {
+                    url: format!("{}/missing", base),
+                    outcome: LinkOutcome::Status(404),
+                },
+            ]
+        );
+        assert!(statuses[0].is_healthy());
+        assert!(!statuses[1].is_healthy());
+    }
+
+    #[test]
+    fn test_lint_heading_hierarchy() {
+        let arena = Arena::new();
+        let clean = "## Intro\n\n### Detail\n\n# Next\n\n## Part\n";
+        let root = extract_ast_from_str(clean, Flavor::CommonMark, &arena);
+        assert_eq!(lint_heading_hierarchy(root), vec![]);
+
+        let skipped = "# Title\n\n### Too deep\n\n#### Fine\n";
+        let root = extract_ast_from_str(skipped, Flavor::CommonMark, &arena);
+        assert_eq!(
+            lint_heading_hierarchy(root),
+            vec![HeadingWarning::SkippedLevel {
+                text: "Too deep".to_string(),
+                from: 1,
+                to: 3,
+                line: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_multiple_top_level() {
+        let arena = Arena::new();
+        let md = "# Title\n\n## Part\n\n# Another\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            lint_multiple_top_level(root),
+            vec![HeadingWarning::MultipleTopLevel {
+                text: "Another".to_string(),
+                line: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_line_length() {
+        let long = "word ".repeat(20);
+        let md = format!(
+            "# Title\n\n{long}\n\n```sh\n{long}\n```\n\n\
+             | a | b |\n|---|:-:|\n| {long} | x |\n\nshort\n{long}\n"
+        );
+
+        assert_eq!(
+            lint_line_length(&md, 80),
+            vec![
+                LineWarning {
+                    line: 3,
+                    length: 100,
+                },
+                LineWarning {
+                    line: 14,
+                    length: 100,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_missing_alt() {
+        let arena = Arena::new();
+        let md = "![A chart](chart.png)\n\n![](spacer.gif) and ![ ](blank.png)\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            lint_missing_alt(root),
+            vec![
+                MissingAlt {
+                    url: "spacer.gif".to_string(),
+                    line: 3,
+                },
+                MissingAlt {
+                    url: "blank.png".to_string(),
+                    line: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_duplicate_headings() {
+        let arena = Arena::new();
+        let md = "# Guide\n\n## Setup\n\n## Usage\n\n## Setup!\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            lint_duplicate_headings(root),
+            vec![DuplicateHeading {
+                slug: "setup".to_string(),
+                texts: vec!["Setup".to_string(), "Setup!".to_string()],
+                lines: vec![3, 7],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let arena = Arena::new();
+        let md = format!(
+            "# Title\n\n![](cover.png)\n\n#### Deep\n\n{}\n",
+            "word ".repeat(20).trim_end()
+        );
+        let root = extract_ast_from_str(&md, Flavor::CommonMark, &arena);
+
+        let diagnostics = lint(root, &md, &LintConfig::default());
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    rule: "missing-alt",
+                    severity: Severity::Error,
+                    message: "image `cover.png` has no alt text".to_string(),
+                    line: Some(3),
+                },
+                Diagnostic {
+                    rule: "heading-hierarchy",
+                    severity: Severity::Warning,
+                    message: "heading `Deep` skips from h1 to h4".to_string(),
+                    line: Some(5),
+                },
+                Diagnostic {
+                    rule: "line-length",
+                    severity: Severity::Note,
+                    message: "line is 99 characters long, more than 80".to_string(),
+                    line: Some(7),
+                },
+            ]
+        );
+
+        let config = LintConfig {
+            missing_alt: false,
+            max_line_length: None,
+            ..LintConfig::default()
+        };
+        let rules: Vec<_> = lint(root, &md, &config).iter().map(|d| d.rule).collect();
+        assert_eq!(rules, vec!["heading-hierarchy"]);
+    }
+}
@@ -1,3 +1,30 @@
-fn main() {
-    println!("Hello, world!");
+/**
+ * ============================================================================
+ * mkforge CLI
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Command-line entry point for parsing and inspecting
+ * Markdown files without writing any Rust.
+ * ============================================================================
+*/
+mod cli;
+
+use clap::Parser;
+use std::io;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args = cli::Cli::parse();
+    let result = match args.command {
+        cli::Command::Run(operation) => cli::run(&operation).map(|output| print!("{}", output)),
+        cli::Command::Watch { operation } => cli::watch(&operation, &mut io::stdout()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
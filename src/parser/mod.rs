@@ -7,23 +7,57 @@
  * an Abstract Syntax Tree (AST) for further processing.
  * ============================================================================
 */
+use crate::analysis::{Heading, extract_headings};
+use crate::ast::{OwnedNode, to_owned_tree};
+use crate::error::MkforgeError;
+use crate::lint::fence_marker;
+use crate::renderer::to_plain_text;
 pub use comrak::Arena;
-use comrak::nodes::AstNode;
+use comrak::nodes::{AstNode, NodeValue};
 use comrak::{ComrakOptions, parse_document};
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
-use std::io::Error;
+use std::io::{self, BufRead, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+#[cfg(feature = "metrics")]
+use std::time::Duration;
 
 /// Supported Markdown flavors for parsing.
-/// Currently only CommonMark and GitHub Flavored Markdown (GFM) are implemented.
+/// Currently CommonMark, GitHub Flavored Markdown (GFM) and MultiMarkdown (MMD)
+/// are implemented.
 ///
 /// This enum can be extended in the future to support more flavors.
+/// `Custom` wraps hand-built options for anything the presets don't cover.
 ///
 /// Specifications for each flavor are pulled from here:
 /// https://github.com/commonmark/commonmark-spec/wiki/markdown-flavors
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub enum Flavor {
     CommonMark,
     GitHub,
+    MultiMarkdown,
+    Custom(ComrakOptions<'static>),
+}
+
+/// ComrakOptions has no notion of equality, so two `Custom` flavors are
+/// compared by their options' `Debug` output. Callbacks in the options
+/// can't be told apart that way. A `Custom` flavor never equals a preset,
+/// even when it carries the preset's options.
+impl PartialEq for Flavor {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Flavor::CommonMark, Flavor::CommonMark)
+            | (Flavor::GitHub, Flavor::GitHub)
+            | (Flavor::MultiMarkdown, Flavor::MultiMarkdown) => true,
+            (Flavor::Custom(options), Flavor::Custom(other)) => {
+                format!("{:?}", options) == format!("{:?}", other)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Flavor {
@@ -32,16 +66,17 @@ impl Flavor {
         match self {
             Flavor::CommonMark => "CommonMark",
             Flavor::GitHub => "GitHub Flavored Markdown",
+            Flavor::MultiMarkdown => "MultiMarkdown",
+            Flavor::Custom(_) => "Custom",
         }
     }
 
     /// Parses a string to return the corresponding Flavor enum.
+    /// `Custom` cannot be reconstructed from its name and always yields `None`.
+    ///
+    /// Thin wrapper around the `FromStr` implementation.
     pub fn from_string(s: &str) -> Option<Self> {
-        match s {
-            "CommonMark" => Some(Flavor::CommonMark),
-            "GitHub" => Some(Flavor::GitHub),
-            _ => None,
-        }
+        s.parse().ok()
     }
 
     /// Converts the Flavor to ComrakOptions for parsing.
@@ -68,30 +103,518 @@ impl Flavor {
                 },
                 ..Default::default()
             },
+
+            // MultiMarkdown (MMD) options.
+            // comrak has no native MMD mode, so this approximates it with
+            // a leading metadata block, tables and footnotes.
+            Flavor::MultiMarkdown => ComrakOptions {
+                extension: comrak::ComrakExtensionOptions {
+                    front_matter_delimiter: Some("---".to_string()),
+                    table: true,
+                    footnotes: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+
+            Flavor::Custom(options) => options.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Flavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_string())
+    }
+}
+
+/// Flavor names are matched case-insensitively and accept common aliases,
+/// since they usually come from config files or command-line flags.
+impl FromStr for Flavor {
+    type Err = MkforgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "commonmark" | "common-mark" | "cm" => Ok(Flavor::CommonMark),
+            "github" | "gfm" | "ghmd" | "github-flavored-markdown" | "github flavored markdown" => {
+                Ok(Flavor::GitHub)
+            }
+            "multimarkdown" | "mmd" => Ok(Flavor::MultiMarkdown),
+            _ => Err(MkforgeError::UnknownFlavor(s.to_string())),
         }
     }
 }
 
+/// How math spans are recognized while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathMode {
+    /// Math syntax is left as plain text.
+    Off,
+    /// `$...$` for inline math and `$$...$$` for display math.
+    Dollars,
+    /// ``$`...`$`` for inline math and ```` ```math ```` fenced blocks.
+    Code,
+}
+
+/// How `[[wikilinks]]` are recognized while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WikilinkStyle {
+    /// Wikilink syntax is left as plain text.
+    Off,
+    /// `[[url|title]]`, as used by Obsidian and MediaWiki.
+    TitleAfterPipe,
+    /// `[[title|url]]`, as used by GitHub wikis.
+    TitleBeforePipe,
+}
+
 ///
 /// Markdown Parser is a container for holding
 /// the state needed for the parser,
 /// including the file path and parse options.
+///
+/// Raw HTML in the document is omitted from rendered output unless
+/// `ParseConfigBuilder::allow_unsafe_html` is enabled. Only enable it
+/// for trusted content: passing user-supplied HTML through allows
+/// script injection (XSS) in whatever page displays the output.
+/// For untrusted input, also cap its size with
+/// `ParseConfigBuilder::max_input_bytes`.
 #[allow(dead_code)]
 pub struct ParseConfig {
     options: ComrakOptions<'static>,
     flavor: Flavor,
-    file_path: String,
+    file_path: PathBuf,
+    max_input_bytes: Option<usize>,
 }
 
 impl ParseConfig {
-    pub fn new(file_path: impl Into<String>, flavor: Flavor) -> Self {
+    pub fn new(file_path: impl AsRef<Path>, flavor: Flavor) -> Self {
         let options = flavor.to_options();
         ParseConfig {
             options,
             flavor,
-            file_path: file_path.into(),
+            file_path: file_path.as_ref().to_path_buf(),
+            max_input_bytes: None,
         }
     }
+
+    /// Returns the path of the file this config parses.
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Starts building a config that can toggle individual extensions
+    /// on top of a flavor. The flavor defaults to CommonMark.
+    pub fn builder(file_path: impl AsRef<Path>) -> ParseConfigBuilder {
+        ParseConfigBuilder {
+            file_path: file_path.as_ref().to_path_buf(),
+            flavor: Flavor::CommonMark,
+            footnotes: None,
+            math_dollars: None,
+            math_code: None,
+            header_anchors: None,
+            smart_punctuation: None,
+            allow_unsafe_html: None,
+            sourcepos: None,
+            description_lists: None,
+            wikilinks: None,
+            superscript: None,
+            subscript: None,
+            autolink: None,
+            hard_breaks: None,
+            front_matter_delimiter: None,
+            max_input_bytes: None,
+        }
+    }
+
+    /// Returns a `Flavor::Custom` carrying this config's exact options,
+    /// for passing to renderers such as `renderer::to_html`.
+    pub fn as_flavor(&self) -> Flavor {
+        Flavor::Custom(self.options.clone())
+    }
+
+    /// Returns this config pointed at another file, keeping its options.
+    pub fn with_file_path(mut self, file_path: impl AsRef<Path>) -> Self {
+        self.file_path = file_path.as_ref().to_path_buf();
+        self
+    }
+}
+
+/// Builder for a `ParseConfig`, created by `ParseConfig::builder`.
+///
+/// Toggles override whatever the flavor sets,
+/// regardless of the order they are called in.
+///
+/// Only syntax comrak implements can be toggled. In particular there is
+/// no toggle for `==highlighted==` text: comrak has no such extension
+/// and no node to parse it into, so it always stays literal text. Its
+/// `underline` extension is for `__underlined__` text instead.
+pub struct ParseConfigBuilder {
+    file_path: PathBuf,
+    flavor: Flavor,
+    footnotes: Option<bool>,
+    math_dollars: Option<bool>,
+    math_code: Option<bool>,
+    header_anchors: Option<Option<String>>,
+    smart_punctuation: Option<bool>,
+    allow_unsafe_html: Option<bool>,
+    sourcepos: Option<bool>,
+    description_lists: Option<bool>,
+    wikilinks: Option<WikilinkStyle>,
+    superscript: Option<bool>,
+    subscript: Option<bool>,
+    autolink: Option<bool>,
+    hard_breaks: Option<bool>,
+    front_matter_delimiter: Option<Option<String>>,
+    max_input_bytes: Option<usize>,
+}
+
+impl ParseConfigBuilder {
+    /// Sets the flavor whose options the toggles are applied on top of.
+    pub fn flavor(mut self, flavor: Flavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Enables or disables footnotes (`[^1]`).
+    pub fn footnotes(mut self, enabled: bool) -> Self {
+        self.footnotes = Some(enabled);
+        self
+    }
+
+    /// Enables or disables `$...$` and `$$...$$` math.
+    pub fn math_dollars(mut self, enabled: bool) -> Self {
+        self.math_dollars = Some(enabled);
+        self
+    }
+
+    /// Selects which math syntax, if any, produces math nodes.
+    pub fn math(mut self, mode: MathMode) -> Self {
+        self.math_dollars = Some(mode == MathMode::Dollars);
+        self.math_code = Some(mode == MathMode::Code);
+        self
+    }
+
+    /// Adds `id` anchors to headings when rendering to HTML, with ids
+    /// prefixed by the given string. `None` disables anchors.
+    ///
+    /// Ids use the same slugs as `analysis::extract_headings`,
+    /// so links from a generated TOC resolve to the rendered headings.
+    pub fn header_anchors(mut self, prefix: Option<String>) -> Self {
+        self.header_anchors = Some(prefix);
+        self
+    }
+
+    /// Converts straight quotes to curly quotes and `--`/`---` to dashes.
+    /// Disabled by default.
+    pub fn smart_punctuation(mut self, enabled: bool) -> Self {
+        self.smart_punctuation = Some(enabled);
+        self
+    }
+
+    /// Passes raw HTML and dangerous URLs through to rendered output.
+    /// Disabled by default; see `ParseConfig` for the security implications.
+    pub fn allow_unsafe_html(mut self, enabled: bool) -> Self {
+        self.allow_unsafe_html = Some(enabled);
+        self
+    }
+
+    /// Adds `data-sourcepos` attributes to elements rendered to HTML,
+    /// so editors can map output back to source lines. Disabled by default.
+    ///
+    /// The parser always records positions, so the `start_line`, ...
+    /// fields of extracted headings, links and images are set either way.
+    pub fn sourcepos(mut self, enabled: bool) -> Self {
+        self.sourcepos = Some(enabled);
+        self
+    }
+
+    /// Enables or disables definition lists (`Term` followed by `: Definition`).
+    pub fn description_lists(mut self, enabled: bool) -> Self {
+        self.description_lists = Some(enabled);
+        self
+    }
+
+    /// Selects which wikilink syntax, if any, produces wikilink nodes.
+    /// A link without a pipe, such as `[[Home]]`, works in either style.
+    pub fn wikilinks(mut self, style: WikilinkStyle) -> Self {
+        self.wikilinks = Some(style);
+        self
+    }
+
+    /// Enables or disables `^superscript^`.
+    pub fn superscript(mut self, enabled: bool) -> Self {
+        self.superscript = Some(enabled);
+        self
+    }
+
+    /// Enables or disables `~subscript~`. Double tildes still mark
+    /// strikethrough when that extension is on as well.
+    pub fn subscript(mut self, enabled: bool) -> Self {
+        self.subscript = Some(enabled);
+        self
+    }
+
+    /// Turns bare URLs such as `https://example.com` and `www.example.com`
+    /// into links, regardless of flavor. On `Flavor::CommonMark` this
+    /// gives "CommonMark plus autolinks", without any other GFM syntax.
+    pub fn autolink(mut self, enabled: bool) -> Self {
+        self.autolink = Some(enabled);
+        self
+    }
+
+    /// Renders every newline inside a paragraph as a `<br>`, as chat and
+    /// comment renderers do, instead of joining the lines with a space.
+    /// Disabled by default.
+    pub fn hard_breaks(mut self, enabled: bool) -> Self {
+        self.hard_breaks = Some(enabled);
+        self
+    }
+
+    /// Treats a block fenced by `delimiter` at the very start of the file,
+    /// such as `---` for YAML, as front matter: it becomes a single
+    /// front matter node instead of content. `None` disables front matter,
+    /// even under flavors that enable it, such as `Flavor::MultiMarkdown`.
+    ///
+    /// To read the front matter itself, see `frontmatter::split_frontmatter`.
+    pub fn front_matter_delimiter(mut self, delimiter: Option<String>) -> Self {
+        self.front_matter_delimiter = Some(delimiter);
+        self
+    }
+
+    /// Rejects input longer than `max_bytes` with
+    /// `MkforgeError::InputTooLarge` instead of parsing it. Reading stops
+    /// as soon as the limit is passed, so an oversized input is never
+    /// held in memory whole. Unlimited by default.
+    ///
+    /// Use this as a basic denial-of-service guard when parsing untrusted
+    /// Markdown, such as comments submitted to a server.
+    pub fn max_input_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_input_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Builds the config, applying every toggle to the flavor's options.
+    pub fn build(self) -> ParseConfig {
+        let mut options = self.flavor.to_options();
+        if let Some(enabled) = self.footnotes {
+            options.extension.footnotes = enabled;
+        }
+        if let Some(enabled) = self.math_dollars {
+            options.extension.math_dollars = enabled;
+        }
+        if let Some(enabled) = self.math_code {
+            options.extension.math_code = enabled;
+        }
+        if let Some(prefix) = self.header_anchors {
+            options.extension.header_ids = prefix;
+        }
+        if let Some(enabled) = self.smart_punctuation {
+            options.parse.smart = enabled;
+        }
+        if let Some(enabled) = self.allow_unsafe_html {
+            options.render.unsafe_ = enabled;
+        }
+        if let Some(enabled) = self.sourcepos {
+            options.render.sourcepos = enabled;
+        }
+        if let Some(enabled) = self.description_lists {
+            options.extension.description_lists = enabled;
+        }
+        if let Some(enabled) = self.superscript {
+            options.extension.superscript = enabled;
+        }
+        if let Some(enabled) = self.subscript {
+            options.extension.subscript = enabled;
+        }
+        if let Some(enabled) = self.autolink {
+            options.extension.autolink = enabled;
+        }
+        if let Some(enabled) = self.hard_breaks {
+            options.render.hardbreaks = enabled;
+        }
+        if let Some(delimiter) = self.front_matter_delimiter {
+            options.extension.front_matter_delimiter = delimiter;
+        }
+        if let Some(style) = self.wikilinks {
+            options.extension.wikilinks_title_after_pipe = style == WikilinkStyle::TitleAfterPipe;
+            options.extension.wikilinks_title_before_pipe = style == WikilinkStyle::TitleBeforePipe;
+        }
+
+        ParseConfig {
+            options,
+            flavor: self.flavor,
+            file_path: self.file_path,
+            max_input_bytes: self.max_input_bytes,
+        }
+    }
+}
+
+/// The settings a TOML config file may hold, all optional.
+///
+/// Unknown keys are rejected so that typos don't silently do nothing.
+#[cfg(feature = "toml")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    flavor: Option<String>,
+    footnotes: Option<bool>,
+    math: Option<String>,
+    smart_punctuation: Option<bool>,
+    allow_unsafe_html: Option<bool>,
+    sourcepos: Option<bool>,
+    description_lists: Option<bool>,
+    superscript: Option<bool>,
+    subscript: Option<bool>,
+    autolink: Option<bool>,
+    hard_breaks: Option<bool>,
+}
+
+#[cfg(feature = "toml")]
+impl ParseConfig {
+    /// Loads a shared config from a TOML file; see `from_toml_str`.
+    pub fn from_toml_file(path: &Path) -> Result<ParseConfig, MkforgeError> {
+        let content = fs::read_to_string(path).map_err(|source| MkforgeError::ReadFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        ParseConfig::from_toml_str(&content)
+    }
+
+    /// Builds a config from TOML such as:
+    ///
+    /// ```toml
+    /// flavor = "gfm"
+    /// footnotes = true
+    /// math = "dollars"   # or "code" or "off"
+    /// smart_punctuation = true
+    /// ```
+    ///
+    /// `flavor` takes the names `Flavor::from_str` accepts, and the other
+    /// keys are the builder toggles of the same name. Keys that are left
+    /// out keep the flavor's defaults. Unknown keys, unknown values and
+    /// malformed TOML are all `MkforgeError::Config`. A config describes
+    /// how to parse rather than what, so its file path is empty; set it
+    /// with `with_file_path`.
+    pub fn from_toml_str(content: &str) -> Result<ParseConfig, MkforgeError> {
+        let file: ConfigFile =
+            toml::from_str(content).map_err(|err| MkforgeError::Config(err.to_string()))?;
+
+        let mut builder = ParseConfig::builder("");
+        if let Some(flavor) = file.flavor {
+            let flavor = flavor
+                .parse()
+                .map_err(|err: MkforgeError| MkforgeError::Config(err.to_string()))?;
+            builder = builder.flavor(flavor);
+        }
+        if let Some(math) = file.math {
+            let mode = match math.as_str() {
+                "off" => MathMode::Off,
+                "dollars" => MathMode::Dollars,
+                "code" => MathMode::Code,
+                _ => {
+                    let message = format!(
+                        "unknown math mode `{}`, expected `off`, `dollars` or `code`",
+                        math
+                    );
+                    return Err(MkforgeError::Config(message));
+                }
+            };
+            builder = builder.math(mode);
+        }
+
+        // Unset toggles are left as `None`, as if their setter was never called.
+        builder.footnotes = file.footnotes;
+        builder.smart_punctuation = file.smart_punctuation;
+        builder.allow_unsafe_html = file.allow_unsafe_html;
+        builder.sourcepos = file.sourcepos;
+        builder.description_lists = file.description_lists;
+        builder.superscript = file.superscript;
+        builder.subscript = file.subscript;
+        builder.autolink = file.autolink;
+        builder.hard_breaks = file.hard_breaks;
+        Ok(builder.build())
+    }
+}
+
+/// A parsed document together with the source text it was parsed from.
+///
+/// comrak's sourcepos data refers to positions in the original text,
+/// so the source must be kept alive to show snippets or re-slice it.
+#[derive(Debug)]
+pub struct ParsedDocument<'a> {
+    pub root: &'a AstNode<'a>,
+    pub source: String,
+}
+
+/// Parses the file for a given parse configuration,
+/// returning both the AST and the source text.
+pub fn parse<'a>(
+    config: &ParseConfig,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<ParsedDocument<'a>, MkforgeError> {
+    // Read the file content
+    let bytes = read_source_file(config)?;
+    let source = decode_utf8(bytes)?;
+
+    // Parse the document using comrak
+    let root = parse_document(arena, &source, &config.options);
+
+    Ok(ParsedDocument { root, source })
+}
+
+/// Like `parse`, but reads the source from `reader` instead of the
+/// config's file, which is ignored. The config's size limit still applies.
+pub fn parse_reader<'a, R: Read>(
+    reader: R,
+    config: &ParseConfig,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<ParsedDocument<'a>, MkforgeError> {
+    let bytes = read_limited(reader, config.max_input_bytes)?;
+    let source = decode_utf8(bytes)?;
+    let root = parse_document(arena, &source, &config.options);
+
+    Ok(ParsedDocument { root, source })
+}
+
+/// Timings and sizes measured by `parse_timed`.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseMetrics {
+    pub read_duration: Duration,
+    pub parse_duration: Duration,
+    pub bytes_read: usize,
+    pub node_count: usize,
+}
+
+/// Like `parse`, but also measures how long reading and parsing took.
+///
+/// `read_duration` covers reading and UTF-8 validation, `parse_duration`
+/// covers comrak alone. Counting nodes walks the whole tree once more,
+/// outside both durations.
+#[cfg(feature = "metrics")]
+pub fn parse_timed<'a>(
+    config: &ParseConfig,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<(ParsedDocument<'a>, ParseMetrics), MkforgeError> {
+    use std::time::Instant;
+
+    let started = Instant::now();
+    let bytes = read_source_file(config)?;
+    let bytes_read = bytes.len();
+    let source = decode_utf8(bytes)?;
+    let read_duration = started.elapsed();
+
+    let started = Instant::now();
+    let root = parse_document(arena, &source, &config.options);
+    let parse_duration = started.elapsed();
+
+    let metrics = ParseMetrics {
+        read_duration,
+        parse_duration,
+        bytes_read,
+        node_count: root.descendants().count(),
+    };
+    Ok((ParsedDocument { root, source }, metrics))
 }
 
 /// Extracts the AST for a given parse configuration.
@@ -101,34 +624,459 @@ impl ParseConfig {
 pub fn extract_ast<'a>(
     config: &ParseConfig,
     arena: &'a Arena<AstNode<'a>>,
-) -> Result<&'a AstNode<'a>, Error> {
-    // Read the file content
-    let md = fs::read_to_string(&config.file_path)?;
+) -> Result<&'a AstNode<'a>, MkforgeError> {
+    parse(config, arena).map(|doc| doc.root)
+}
 
-    // Parse the document using comrak
-    let ast = parse_document(arena, &md, &config.options);
+/// Recursively parses every `.md` and `.markdown` file under `root`,
+/// returning the documents sorted by path.
+///
+/// All documents are allocated in the caller's `arena`, so they stay
+/// valid for as long as it does and can be freely returned together.
+/// Directory symlinks are followed; a symlink pointing back into one of
+/// its own ancestors is reported as `MkforgeError::SymlinkLoop`, and an
+/// unreadable file or directory as `MkforgeError::ReadFile`.
+pub fn parse_dir<'a>(
+    root: &Path,
+    flavor: Flavor,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<Vec<(PathBuf, ParsedDocument<'a>)>, MkforgeError> {
+    let mut files = Vec::new();
+    collect_markdown_files(root, &mut HashSet::new(), &mut files)?;
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let config = ParseConfig::new(&path, flavor.clone());
+            let doc = parse(&config, arena)?;
+            Ok((path, doc))
+        })
+        .collect()
+}
+
+/// Collects Markdown files under `dir` into `files`.
+/// `ancestors` holds the canonical paths of the directories being walked.
+fn collect_markdown_files(
+    dir: &Path,
+    ancestors: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), MkforgeError> {
+    let canonical = fs::canonicalize(dir).map_err(read_error(dir))?;
+    if !ancestors.insert(canonical.clone()) {
+        return Err(MkforgeError::SymlinkLoop {
+            path: dir.display().to_string(),
+        });
+    }
+
+    for entry in fs::read_dir(dir).map_err(read_error(dir))? {
+        let path = entry.map_err(read_error(dir))?.path();
+        // Follows symlinks, so linked directories are walked too.
+        let metadata = fs::metadata(&path).map_err(read_error(&path))?;
+        if metadata.is_dir() {
+            collect_markdown_files(&path, ancestors, files)?;
+        } else if is_markdown_file(&path) {
+            files.push(path);
+        }
+    }
+
+    ancestors.remove(&canonical);
+    Ok(())
+}
+
+fn is_markdown_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("md" | "markdown")
+    )
+}
+
+fn read_error(path: &Path) -> impl FnOnce(io::Error) -> MkforgeError + '_ {
+    move |source| MkforgeError::ReadFile {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+/// An arena-free snapshot of a parsed document.
+///
+/// comrak ASTs borrow from their arena and cannot cross threads, so
+/// this keeps an owned copy of the tree and data derived from it
+/// instead. The source is kept too, so the borrowed AST can be rebuilt
+/// with `extract_ast_from_str` when comrak's APIs are needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedDocument {
+    pub path: PathBuf,
+    pub source: String,
+    pub root: OwnedNode,
+    pub plain_text: String,
+    pub headings: Vec<Heading>,
+}
+
+/// Parses the file at `path` into an `OwnedDocument`.
+/// The AST is built in a temporary arena that is dropped before returning.
+pub fn parse_owned(path: &Path, flavor: Flavor) -> Result<OwnedDocument, MkforgeError> {
+    let arena = Arena::new();
+    let config = ParseConfig::new(path, flavor);
+    let doc = parse(&config, &arena)?;
+
+    Ok(OwnedDocument {
+        path: path.to_path_buf(),
+        root: to_owned_tree(doc.root),
+        plain_text: to_plain_text(doc.root),
+        headings: extract_headings(doc.root),
+        source: doc.source,
+    })
+}
+
+/// Parses many files across rayon's thread pool.
+///
+/// Each file gets its own arena on the worker thread that parses it,
+/// and only the owned snapshot is sent back. This costs a conversion per
+/// document, but is the only way to parallelize since comrak ASTs are
+/// `!Send`. Results are returned in the same order as `paths`.
+#[cfg(feature = "rayon")]
+pub fn parse_files_parallel(
+    paths: &[PathBuf],
+    flavor: Flavor,
+) -> Vec<Result<OwnedDocument, MkforgeError>> {
+    use rayon::prelude::*;
+
+    paths
+        .par_iter()
+        .map(|path| parse_owned(path, flavor.clone()))
+        .collect()
+}
+
+/// Guesses the flavor a document was written for.
+///
+/// This is a best-effort heuristic: the content is parsed as GFM and
+/// `Flavor::GitHub` is returned if that produces anything CommonMark
+/// lacks, i.e. a table, a task list item, strikethrough or an extended
+/// autolink such as a bare `https://` URL. Everything else, including
+/// MultiMarkdown, is reported as `Flavor::CommonMark`. Since the syntax
+/// is recognized by the real parser, text inside code is never mistaken
+/// for a GFM feature.
+pub fn detect_flavor(content: &str) -> Flavor {
+    let arena = Arena::new();
+    let gfm = extract_ast_from_str(content, Flavor::GitHub, &arena);
+    let mut gfm_links = 0;
+    for node in gfm.descendants() {
+        match node.data.borrow().value {
+            NodeValue::Table(_) | NodeValue::TaskItem(_) | NodeValue::Strikethrough => {
+                return Flavor::GitHub;
+            }
+            NodeValue::Link(_) => gfm_links += 1,
+            _ => {}
+        }
+    }
+
+    // Extended autolinks are plain links, so compare against CommonMark.
+    let commonmark = extract_ast_from_str(content, Flavor::CommonMark, &arena);
+    let commonmark_links = commonmark
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Link(_)))
+        .count();
+    if gfm_links > commonmark_links {
+        Flavor::GitHub
+    } else {
+        Flavor::CommonMark
+    }
+}
+
+/// Reparses only the top-level blocks of a document touched by an edit.
+///
+/// `previous` is the tree of the document before the edit and `content`
+/// the full text after it, with `changed_byte_range` covering the new
+/// text in `content`. Blocks that end before the edit or start after it
+/// are copied from `previous`, with line numbers shifted past the edit
+/// where needed. Everything in between, including blocks directly
+/// adjacent to the edit, is reparsed on its own and spliced in.
+///
+/// Since the reparsed region is parsed in isolation, it can't see
+/// reference link definitions elsewhere in the document, and an edit
+/// that changes how surrounding blocks parse (such as opening a code
+/// fence) isn't propagated. Reparse the whole document when in doubt.
+pub fn reparse_block(
+    previous: &OwnedNode,
+    content: &str,
+    changed_byte_range: Range<usize>,
+    flavor: Flavor,
+) -> OwnedNode {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+    let line_count = content.lines().count();
+    let line_delta = line_count as isize - previous.sourcepos.end.line as isize;
+
+    let start = changed_byte_range.start.min(content.len());
+    let end = changed_byte_range.end.clamp(start, content.len());
+    let first_line = line_of(start);
+    let last_line = line_of(end.saturating_sub(1).max(start));
+    // The last line the edit touched, counted before the edit.
+    let old_last_line = (last_line as isize - line_delta).max(first_line as isize) as usize;
+
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    for block in &previous.children {
+        if block.sourcepos.end.line + 1 < first_line {
+            before.push(block.clone());
+        } else if block.sourcepos.start.line > old_last_line + 1 {
+            let mut block = block.clone();
+            shift_lines(&mut block, line_delta);
+            after.push(block);
+        }
+    }
+
+    let region_start = before
+        .last()
+        .map_or(1, |block| block.sourcepos.end.line + 1);
+    let region_end = after
+        .first()
+        .map_or(line_count, |block| block.sourcepos.start.line - 1);
+    let region = if region_start <= region_end {
+        let from = line_starts
+            .get(region_start - 1)
+            .copied()
+            .unwrap_or(content.len());
+        let to = line_starts
+            .get(region_end)
+            .copied()
+            .unwrap_or(content.len());
+        &content[from..to]
+    } else {
+        ""
+    };
+
+    let arena = Arena::new();
+    let reparsed = to_owned_tree(extract_ast_from_str(region, flavor, &arena));
+    let reparsed = reparsed.children.into_iter().map(|mut block| {
+        shift_lines(&mut block, region_start as isize - 1);
+        block
+    });
+
+    let mut sourcepos = previous.sourcepos;
+    sourcepos.end.line = line_count;
+    OwnedNode {
+        value: previous.value.clone(),
+        sourcepos,
+        children: before.into_iter().chain(reparsed).chain(after).collect(),
+    }
+}
+
+/// Moves a node and all its descendants `delta` lines down the source.
+fn shift_lines(node: &mut OwnedNode, delta: isize) {
+    node.sourcepos.start.line = node.sourcepos.start.line.saturating_add_signed(delta);
+    node.sourcepos.end.line = node.sourcepos.end.line.saturating_add_signed(delta);
+    for child in &mut node.children {
+        shift_lines(child, delta);
+    }
+}
+
+/// Extracts the AST from Markdown that is already in memory.
+/// Since no I/O is involved, parsing cannot fail.
+pub fn extract_ast_from_str<'a>(
+    content: &str,
+    flavor: Flavor,
+    arena: &'a Arena<AstNode<'a>>,
+) -> &'a AstNode<'a> {
+    parse_document(arena, content, &flavor.to_options())
+}
+
+/// Extracts the AST from any `Read` source, such as stdin or a decoder.
+/// Input that is not valid UTF-8 yields `MkforgeError::NotUtf8`.
+pub fn extract_ast_from_reader<'a, R: Read>(
+    reader: &mut R,
+    flavor: Flavor,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<&'a AstNode<'a>, MkforgeError> {
+    // Read the whole source into memory
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let md = decode_utf8(bytes)?;
 
-    // Return the AST
-    Ok(ast)
+    Ok(extract_ast_from_str(&md, flavor, arena))
+}
+
+/// Parses Markdown from `reader` a block at a time, passing each
+/// top-level block to `callback` as soon as it is parsed.
+///
+/// Input is split into chunks at blank lines outside fenced code blocks,
+/// and each chunk is parsed on its own arena that is dropped afterwards,
+/// so memory stays bounded by the largest chunk rather than the file.
+/// The trade-off is that parsing happens without the rest of the
+/// document: constructs that span a blank line, such as loose lists,
+/// indented code with blank lines or HTML blocks like `<pre>`, arrive
+/// split into several blocks, and reference links only resolve against
+/// definitions in their own chunk. A long table has no blank lines and
+/// stays one chunk, so it is held in memory whole.
+pub fn parse_streaming<R: BufRead>(
+    reader: R,
+    flavor: Flavor,
+    mut callback: impl for<'a> FnMut(&'a AstNode<'a>),
+) -> Result<(), MkforgeError> {
+    let options = flavor.to_options();
+    stream_chunks(reader, |chunk| {
+        let arena = Arena::new();
+        for block in parse_document(&arena, chunk, &options).children() {
+            callback(block);
+        }
+    })
+}
+
+/// Reads `reader` line by line, passing on each blank-line separated
+/// chunk of Markdown and reusing a single buffer for all of them.
+fn stream_chunks<R: BufRead>(
+    mut reader: R,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<(), MkforgeError> {
+    let mut chunk = String::new();
+    let mut line = Vec::new();
+    let mut open_fence: Option<(char, usize)> = None;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let text = std::str::from_utf8(&line).map_err(|_| MkforgeError::NotUtf8)?;
+
+        if let Some((marker, len)) = open_fence {
+            if fence_marker(text).is_some_and(|(c, n)| c == marker && n >= len) {
+                open_fence = None;
+            }
+        } else if let Some(marker) = fence_marker(text) {
+            open_fence = Some(marker);
+        } else if text.trim().is_empty() {
+            if !chunk.is_empty() {
+                on_chunk(&chunk);
+                chunk.clear();
+            }
+            continue;
+        }
+        chunk.push_str(text);
+    }
+
+    if !chunk.is_empty() {
+        on_chunk(&chunk);
+    }
+    Ok(())
+}
+
+/// Reads the config's file, enforcing its size limit.
+fn read_source_file(config: &ParseConfig) -> Result<Vec<u8>, MkforgeError> {
+    let path = &config.file_path;
+    let file = fs::File::open(path).map_err(read_error(path))?;
+    read_limited(file, config.max_input_bytes).map_err(|err| match err {
+        MkforgeError::Io(source) => read_error(path)(source),
+        err => err,
+    })
+}
+
+/// Reads all of `reader`, or fails with `MkforgeError::InputTooLarge`
+/// once more than `max_input_bytes` have been read.
+fn read_limited<R: Read>(
+    mut reader: R,
+    max_input_bytes: Option<usize>,
+) -> Result<Vec<u8>, MkforgeError> {
+    let mut bytes = Vec::new();
+    let Some(max_bytes) = max_input_bytes else {
+        reader.read_to_end(&mut bytes)?;
+        return Ok(bytes);
+    };
+
+    // A single byte past the limit is enough to reject the input.
+    reader.take(max_bytes as u64 + 1).read_to_end(&mut bytes)?;
+    if bytes.len() > max_bytes {
+        return Err(MkforgeError::InputTooLarge { max_bytes });
+    }
+    Ok(bytes)
+}
+
+/// Converts raw bytes into Markdown source, rejecting invalid UTF-8.
+fn decode_utf8(bytes: Vec<u8>) -> Result<String, MkforgeError> {
+    String::from_utf8(bytes).map_err(|_| MkforgeError::NotUtf8)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_flavor_eq() {
+        let custom = Flavor::Custom(Flavor::GitHub.to_options());
+        assert_eq!(custom.clone(), custom);
+        assert_ne!(custom, Flavor::GitHub);
+
+        let mut options = Flavor::GitHub.to_options();
+        options.extension.footnotes = !options.extension.footnotes;
+        assert_ne!(custom, Flavor::Custom(options));
+        assert_ne!(Flavor::CommonMark, Flavor::GitHub);
+    }
+
     #[test]
     fn test_flavor_as_string() {
         assert_eq!(Flavor::CommonMark.as_string(), "CommonMark");
         assert_eq!(Flavor::GitHub.as_string(), "GitHub Flavored Markdown");
+        assert_eq!(Flavor::MultiMarkdown.as_string(), "MultiMarkdown");
+        assert_eq!(
+            Flavor::Custom(ComrakOptions::default()).as_string(),
+            "Custom"
+        );
     }
 
     #[test]
     fn test_flavor_from_string() {
         assert_eq!(Flavor::from_string("CommonMark"), Some(Flavor::CommonMark));
         assert_eq!(Flavor::from_string("GitHub"), Some(Flavor::GitHub));
+        assert_eq!(
+            Flavor::from_string("MultiMarkdown"),
+            Some(Flavor::MultiMarkdown)
+        );
+        assert_eq!(Flavor::from_string("Custom"), None);
         assert_eq!(Flavor::from_string("Unknown"), None);
     }
 
+    #[test]
+    fn test_flavor_from_string_aliases() {
+        assert_eq!(Flavor::from_string("github"), Some(Flavor::GitHub));
+        assert_eq!(Flavor::from_string("GFM"), Some(Flavor::GitHub));
+        assert_eq!(Flavor::from_string("gfm"), Some(Flavor::GitHub));
+        assert_eq!(Flavor::from_string("ghmd"), Some(Flavor::GitHub));
+        assert_eq!(
+            Flavor::from_string("github-flavored-markdown"),
+            Some(Flavor::GitHub)
+        );
+        assert_eq!(Flavor::from_string("commonmark"), Some(Flavor::CommonMark));
+        assert_eq!(Flavor::from_string("COMMONMARK"), Some(Flavor::CommonMark));
+        assert_eq!(Flavor::from_string("cm"), Some(Flavor::CommonMark));
+        assert_eq!(Flavor::from_string("Common-Mark"), Some(Flavor::CommonMark));
+        assert_eq!(Flavor::from_string("mmd"), Some(Flavor::MultiMarkdown));
+        assert_eq!(Flavor::from_string("markdown"), None);
+    }
+
+    #[test]
+    fn test_flavor_display_round_trip() {
+        for flavor in [Flavor::CommonMark, Flavor::GitHub, Flavor::MultiMarkdown] {
+            assert_eq!(flavor.to_string().parse::<Flavor>().unwrap(), flavor);
+        }
+    }
+
+    #[test]
+    fn test_flavor_from_str() {
+        assert_eq!("GitHub".parse::<Flavor>().unwrap(), Flavor::GitHub);
+        assert!(matches!(
+            "Unknown".parse::<Flavor>(),
+            Err(MkforgeError::UnknownFlavor(ref name)) if name == "Unknown"
+        ));
+    }
+
+    #[test]
+    fn test_flavor_display() {
+        assert_eq!(format!("{}", Flavor::CommonMark), "CommonMark");
+        assert_eq!(Flavor::GitHub.to_string(), "GitHub Flavored Markdown");
+    }
+
     #[test]
     fn test_flavor_to_options() {
         let commonmark_options = Flavor::CommonMark.to_options();
@@ -138,15 +1086,41 @@ mod tests {
         assert!(github_options.extension.table);
         assert!(github_options.extension.strikethrough);
         assert!(github_options.render.github_pre_lang);
+
+        let mmd_options = Flavor::MultiMarkdown.to_options();
+        assert!(mmd_options.extension.footnotes);
+        assert!(mmd_options.extension.table);
+        assert_eq!(
+            mmd_options.extension.front_matter_delimiter.as_deref(),
+            Some("---")
+        );
+
+        let mut options = ComrakOptions::default();
+        options.extension.strikethrough = true;
+        options.extension.math_dollars = true;
+        let custom_options = Flavor::Custom(options).to_options();
+        assert!(custom_options.extension.strikethrough);
+        assert!(custom_options.extension.math_dollars);
+        assert!(!custom_options.extension.table);
     }
 
     #[test]
     fn test_parse_config_new() {
         let config = ParseConfig::new("test.md", Flavor::GitHub);
-        assert_eq!(config.file_path, "test.md");
+        assert_eq!(config.file_path(), Path::new("test.md"));
         assert_eq!(config.flavor.as_string(), "GitHub Flavored Markdown");
     }
 
+    #[test]
+    fn test_parse_config_from_path_buf() {
+        let path = PathBuf::from("docs").join("intro.md");
+        let config = ParseConfig::new(&path, Flavor::CommonMark);
+        assert_eq!(config.file_path(), path.as_path());
+
+        let config = ParseConfig::builder(path.clone()).build();
+        assert_eq!(config.file_path(), path.as_path());
+    }
+
     #[test]
     fn test_extract_ast() {
         let arena = Arena::new();
@@ -162,4 +1136,579 @@ mod tests {
         // Clean up the temporary file
         std::fs::remove_file(temp_file_path).unwrap();
     }
+
+    #[test]
+    fn test_parse_config_builder() {
+        let config = ParseConfig::builder("test.md")
+            .flavor(Flavor::GitHub)
+            .footnotes(true)
+            .math_dollars(true)
+            .build();
+        assert_eq!(config.file_path(), Path::new("test.md"));
+        assert_eq!(config.flavor, Flavor::GitHub);
+        assert!(config.options.extension.footnotes);
+        assert!(config.options.extension.math_dollars);
+        // Options from the GitHub flavor are kept.
+        assert!(config.options.extension.table);
+
+        let config = ParseConfig::builder("test.md")
+            .footnotes(false)
+            .flavor(Flavor::MultiMarkdown)
+            .build();
+        assert!(!config.options.extension.footnotes);
+    }
+
+    #[test]
+    fn test_parse_config_math_mode() {
+        let has_math = |mode| {
+            let arena = Arena::new();
+            let config = ParseConfig::builder("test.md").math(mode).build();
+            let root = parse_document(&arena, "$x^2$", &config.options);
+            root.descendants()
+                .any(|node| matches!(node.data.borrow().value, NodeValue::Math(_)))
+        };
+
+        assert!(has_math(MathMode::Dollars));
+        assert!(!has_math(MathMode::Off));
+        assert!(!has_math(MathMode::Code));
+    }
+
+    /// Parses `md` with the config's options and renders it to HTML.
+    fn render_with(config: &ParseConfig, md: &str) -> String {
+        let arena = Arena::new();
+        let root = parse_document(&arena, md, &config.options);
+        crate::renderer::to_html(root, config.as_flavor())
+    }
+
+    #[test]
+    fn test_parse_config_smart_punctuation() {
+        let default = ParseConfig::builder("test.md").build();
+        assert!(!default.options.parse.smart);
+        assert_eq!(
+            render_with(&default, "\"hello\" -- world"),
+            "<p>&quot;hello&quot; -- world</p>\n"
+        );
+
+        let smart = ParseConfig::builder("test.md")
+            .smart_punctuation(true)
+            .build();
+        assert_eq!(
+            render_with(&smart, "\"hello\" -- world"),
+            "<p>\u{201c}hello\u{201d} \u{2013} world</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_allow_unsafe_html() {
+        let md = "<script>alert(1)</script>\n";
+
+        let safe = ParseConfig::builder("test.md").build();
+        assert!(!safe.options.render.unsafe_);
+        assert!(!render_with(&safe, md).contains("<script>"));
+
+        let unsafe_html = ParseConfig::builder("test.md")
+            .allow_unsafe_html(true)
+            .build();
+        assert!(render_with(&unsafe_html, md).contains("<script>alert(1)</script>"));
+    }
+
+    #[test]
+    fn test_parse_config_sourcepos() {
+        let md = "text\n\n# Title\n";
+        assert_eq!(
+            render_with(&ParseConfig::builder("test.md").build(), md),
+            "<p>text</p>\n<h1>Title</h1>\n"
+        );
+
+        let config = ParseConfig::builder("test.md").sourcepos(true).build();
+        assert_eq!(
+            render_with(&config, md),
+            "<p data-sourcepos=\"1:1-1:4\">text</p>\n<h1 data-sourcepos=\"3:1-3:7\">Title</h1>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_description_lists() {
+        let md = "Term\n: Definition\n";
+        let default = ParseConfig::builder("test.md").build();
+        assert_eq!(render_with(&default, md), "<p>Term\n: Definition</p>\n");
+
+        let config = ParseConfig::builder("test.md")
+            .description_lists(true)
+            .build();
+        assert!(render_with(&config, md).starts_with("<dl>\n<dt>Term</dt>\n<dd>Definition</dd>"));
+    }
+
+    #[test]
+    fn test_parse_config_wikilinks() {
+        use crate::analysis::extract_links;
+
+        let links = |style: WikilinkStyle, md: &str| {
+            let config = ParseConfig::builder("test.md").wikilinks(style).build();
+            let arena = Arena::new();
+            let root = parse_document(&arena, md, &config.options);
+            extract_links(root)
+                .into_iter()
+                .map(|link| (link.url, link.text, link.wikilink))
+                .collect::<Vec<_>>()
+        };
+
+        let home = ("Home".to_string(), "Home".to_string(), true);
+        assert_eq!(
+            links(WikilinkStyle::TitleAfterPipe, "[[Home]]"),
+            vec![home.clone()]
+        );
+        assert_eq!(
+            links(WikilinkStyle::TitleBeforePipe, "[[Home]]"),
+            vec![home]
+        );
+        assert_eq!(links(WikilinkStyle::Off, "[[Home]]"), vec![]);
+        assert_eq!(
+            links(WikilinkStyle::TitleAfterPipe, "[[page|Title]]"),
+            vec![("page".to_string(), "Title".to_string(), true)]
+        );
+        assert_eq!(
+            links(WikilinkStyle::TitleBeforePipe, "[[Title|page]]"),
+            vec![("page".to_string(), "Title".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn test_parse_config_superscript_subscript() {
+        let node_types = |config: &ParseConfig, md: &str| {
+            let arena = Arena::new();
+            let root = parse_document(&arena, md, &config.options);
+            root.descendants()
+                .map(|node| crate::ast::node_type_name(&node.data.borrow().value))
+                .collect::<Vec<_>>()
+        };
+        let md = "x^2^ and H~2~O";
+
+        let default = ParseConfig::builder("test.md").build();
+        assert_eq!(
+            node_types(&default, md),
+            vec!["document", "paragraph", "text"]
+        );
+
+        let config = ParseConfig::builder("test.md")
+            .superscript(true)
+            .subscript(true)
+            .build();
+        let types = node_types(&config, md);
+        assert!(types.contains(&"superscript"));
+        assert!(types.contains(&"subscript"));
+        assert_eq!(
+            render_with(&config, md),
+            "<p>x<sup>2</sup> and H<sub>2</sub>O</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_autolink() {
+        let md = "Visit https://example.com today";
+        let link_urls = |config: &ParseConfig| {
+            let arena = Arena::new();
+            let root = parse_document(&arena, md, &config.options);
+            crate::analysis::extract_links(root)
+                .into_iter()
+                .map(|link| link.url)
+                .collect::<Vec<_>>()
+        };
+
+        let off = ParseConfig::builder("test.md").autolink(false).build();
+        assert!(link_urls(&off).is_empty());
+
+        let on = ParseConfig::builder("test.md").autolink(true).build();
+        assert_eq!(link_urls(&on), vec!["https://example.com"]);
+        // Other GFM extensions stay off.
+        assert!(!on.options.extension.table);
+
+        let gfm_off = ParseConfig::builder("test.md")
+            .flavor(Flavor::GitHub)
+            .autolink(false)
+            .build();
+        assert!(link_urls(&gfm_off).is_empty());
+    }
+
+    #[test]
+    fn test_parse_config_hard_breaks() {
+        let md = "first line\nsecond line";
+
+        let default = ParseConfig::builder("test.md").build();
+        assert!(!default.options.render.hardbreaks);
+        // A soft break stays a newline, which browsers show as a space.
+        assert_eq!(
+            render_with(&default, md),
+            "<p>first line\nsecond line</p>\n"
+        );
+
+        let config = ParseConfig::builder("test.md").hard_breaks(true).build();
+        assert_eq!(
+            render_with(&config, md),
+            "<p>first line<br />\nsecond line</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_front_matter_delimiter() {
+        let md = "---\ntitle: Hello\n---\n\nBody text.\n";
+        let node_types = |config: &ParseConfig| {
+            let arena = Arena::new();
+            let root = parse_document(&arena, md, &config.options);
+            root.children()
+                .map(|node| crate::ast::node_type_name(&node.data.borrow().value))
+                .collect::<Vec<_>>()
+        };
+
+        // Without a delimiter the fences are a thematic break and a heading.
+        let default = ParseConfig::builder("test.md").build();
+        assert_eq!(
+            node_types(&default),
+            vec!["thematic_break", "heading", "paragraph"]
+        );
+
+        let config = ParseConfig::builder("test.md")
+            .front_matter_delimiter(Some("---".to_string()))
+            .build();
+        assert_eq!(node_types(&config), vec!["frontmatter", "paragraph"]);
+        assert_eq!(render_with(&config, md), "<p>Body text.</p>\n");
+
+        let mmd_off = ParseConfig::builder("test.md")
+            .flavor(Flavor::MultiMarkdown)
+            .front_matter_delimiter(None)
+            .build();
+        assert_eq!(mmd_off.options.extension.front_matter_delimiter, None);
+    }
+
+    #[test]
+    fn test_parse_config_header_anchors() {
+        use crate::analysis::extract_headings;
+
+        let config = ParseConfig::builder("test.md")
+            .header_anchors(Some("doc-".to_string()))
+            .build();
+        let arena = Arena::new();
+        let root = parse_document(&arena, "## Intro\n\n## Intro\n", &config.options);
+
+        let html = render_with(&config, "## Intro\n\n## Intro\n");
+        for heading in extract_headings(root) {
+            assert!(html.contains(&format!("id=\"doc-{}\"", heading.slug)));
+        }
+        assert!(html.contains("id=\"doc-intro\""));
+        assert!(html.contains("id=\"doc-intro-1\""));
+    }
+
+    #[test]
+    fn test_parse_keeps_source() {
+        let arena = Arena::new();
+        let temp_file_path = "test_parse_keeps_source.md";
+        std::fs::write(temp_file_path, "# Heading\n\nSome content.").unwrap();
+
+        let config = ParseConfig::new(temp_file_path, Flavor::CommonMark);
+        let doc = parse(&config, &arena).unwrap();
+        assert_eq!(doc.source, "# Heading\n\nSome content.");
+        assert_eq!(doc.root.children().count(), 2);
+
+        std::fs::remove_file(temp_file_path).unwrap();
+    }
+
+    #[test]
+    fn test_extract_ast_missing_file() {
+        let arena = Arena::new();
+        let config = ParseConfig::new("does_not_exist.md", Flavor::CommonMark);
+        let err = extract_ast(&config, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::ReadFile { .. }));
+        assert!(err.to_string().contains("does_not_exist.md"));
+    }
+
+    #[test]
+    fn test_parse_config_max_input_bytes() {
+        let arena = Arena::new();
+        let temp_file_path = "test_parse_config_max_input_bytes.md";
+        std::fs::write(temp_file_path, "# Heading\n\nSome content.").unwrap();
+
+        let config = ParseConfig::builder(temp_file_path)
+            .max_input_bytes(10)
+            .build();
+        let err = extract_ast(&config, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::InputTooLarge { max_bytes: 10 }));
+
+        let config = ParseConfig::builder(temp_file_path)
+            .max_input_bytes(24)
+            .build();
+        assert!(extract_ast(&config, &arena).is_ok());
+        std::fs::remove_file(temp_file_path).unwrap();
+
+        // Reading stops one byte past the limit.
+        let config = ParseConfig::builder("").max_input_bytes(10).build();
+        let mut reader = io::Cursor::new(vec![b'a'; 1000]);
+        let err = parse_reader(&mut reader, &config, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::InputTooLarge { .. }));
+        assert_eq!(reader.position(), 11);
+    }
+
+    /// Creates an empty directory under the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mkforge-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_parse_timed() {
+        let dir = temp_dir("parse-timed");
+        let path = dir.join("doc.md");
+        fs::write(&path, "# Title\n\nSome *timed* text.\n").unwrap();
+
+        let arena = Arena::new();
+        let config = ParseConfig::new(&path, Flavor::CommonMark);
+        let (doc, metrics) = parse_timed(&config, &arena).unwrap();
+        assert_eq!(
+            metrics.bytes_read,
+            fs::metadata(&path).unwrap().len() as usize
+        );
+        assert_eq!(metrics.bytes_read, doc.source.len());
+        assert!(metrics.node_count > 0);
+        assert_eq!(metrics.node_count, doc.root.descendants().count());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        let dir = temp_dir("parse-dir");
+        fs::create_dir_all(dir.join("guide/nested")).unwrap();
+        fs::write(dir.join("index.md"), "# Index").unwrap();
+        fs::write(dir.join("guide/setup.markdown"), "# Setup").unwrap();
+        fs::write(dir.join("guide/nested/deep.md"), "# Deep").unwrap();
+        fs::write(dir.join("guide/notes.txt"), "not markdown").unwrap();
+
+        let arena = Arena::new();
+        let docs = parse_dir(&dir, Flavor::CommonMark, &arena).unwrap();
+        let paths: Vec<_> = docs
+            .iter()
+            .map(|(path, _)| path.strip_prefix(&dir).unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("guide/nested/deep.md"),
+                Path::new("guide/setup.markdown"),
+                Path::new("index.md"),
+            ]
+        );
+        assert_eq!(docs[2].1.source, "# Index");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_dir_symlink_loop() {
+        let dir = temp_dir("parse-dir-loop");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+
+        let arena = Arena::new();
+        let err = parse_dir(&dir, Flavor::CommonMark, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::SymlinkLoop { .. }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_owned() {
+        let dir = temp_dir("parse-owned");
+        let path = dir.join("doc.md");
+        fs::write(&path, "# Title\n\nSome *text*.").unwrap();
+
+        let doc = parse_owned(&path, Flavor::CommonMark).unwrap();
+        assert_eq!(doc.path, path);
+        assert_eq!(doc.plain_text, "Title\nSome text.");
+        assert_eq!(doc.headings[0].slug, "title");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parse_files_parallel_keeps_order() {
+        let dir = temp_dir("parse-parallel");
+        let paths: Vec<PathBuf> = (0..50)
+            .map(|i| {
+                let path = dir.join(format!("doc-{}.md", i));
+                fs::write(&path, format!("# Doc {}", i)).unwrap();
+                path
+            })
+            .collect();
+
+        let docs = parse_files_parallel(&paths, Flavor::CommonMark);
+        assert_eq!(docs.len(), 50);
+        for (i, doc) in docs.into_iter().enumerate() {
+            let doc = doc.unwrap();
+            assert_eq!(doc.path, paths[i]);
+            assert_eq!(doc.headings[0].text, format!("Doc {}", i));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_ast_from_str() {
+        let arena = Arena::new();
+        let ast = extract_ast_from_str("# Heading", Flavor::CommonMark, &arena);
+
+        let heading = ast.first_child().unwrap();
+        assert!(matches!(
+            heading.data.borrow().value,
+            NodeValue::Heading(ref h) if h.level == 1
+        ));
+    }
+
+    #[test]
+    fn test_detect_flavor() {
+        let plain = "# Title\n\nSome *text* with a [link](https://a.example).\n";
+        assert_eq!(detect_flavor(plain), Flavor::CommonMark);
+
+        assert_eq!(
+            detect_flavor("| a | b |\n|---|---|\n| 1 | 2 |\n"),
+            Flavor::GitHub
+        );
+        assert_eq!(detect_flavor("- [ ] todo\n"), Flavor::GitHub);
+        assert_eq!(detect_flavor("very ~~wrong~~ right\n"), Flavor::GitHub);
+        assert_eq!(detect_flavor("See https://a.example\n"), Flavor::GitHub);
+
+        // GFM syntax inside code doesn't count.
+        assert_eq!(
+            detect_flavor("```\n| a | b |\n|---|---|\n```\n"),
+            Flavor::CommonMark
+        );
+    }
+
+    #[test]
+    fn test_reparse_block() {
+        let owned = |md: &str| {
+            let arena = Arena::new();
+            to_owned_tree(extract_ast_from_str(md, Flavor::CommonMark, &arena))
+        };
+        let old = "One.\n\nTwo.\n\nThree *3*.\n";
+        let previous = owned(old);
+
+        let new = "One.\n\nTwo, now\nwith *two* lines.\n\nThree *3*.\n";
+        let changed = 6..new.find("\n\nThree").unwrap();
+        let reparsed = reparse_block(&previous, new, changed, Flavor::CommonMark);
+
+        assert_eq!(reparsed, owned(new));
+        assert_eq!(reparsed.children[0], previous.children[0]);
+        // Reused structurally, only moved down a line.
+        assert_eq!(
+            reparsed.children[2].children,
+            previous.children[2]
+                .children
+                .iter()
+                .map(|child| {
+                    let mut child = child.clone();
+                    shift_lines(&mut child, 1);
+                    child
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // Deleting lines and editing at the very start work too.
+        let new = "Uno.\n\nThree *3*.\n";
+        let reparsed = reparse_block(&previous, new, 0..4, Flavor::CommonMark);
+        assert_eq!(reparsed, owned(new));
+    }
+
+    #[test]
+    fn test_extract_ast_from_reader() {
+        use std::io::Cursor;
+
+        let arena = Arena::new();
+        let mut reader = Cursor::new(&b"# Heading\n\nSome content."[..]);
+        let ast = extract_ast_from_reader(&mut reader, Flavor::CommonMark, &arena).unwrap();
+        assert_eq!(ast.children().count(), 2);
+
+        let mut invalid = Cursor::new(&[0xff, 0xfe, 0xfd][..]);
+        let err = extract_ast_from_reader(&mut invalid, Flavor::CommonMark, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::NotUtf8));
+    }
+
+    #[test]
+    fn test_parse_streaming() {
+        use std::io::Cursor;
+
+        let paragraph = "A paragraph of *streamed* text.\n\n";
+        let md = paragraph.repeat(10_000) + "```\ncode\n\nstill code\n```\n";
+
+        let mut largest_chunk = 0;
+        stream_chunks(Cursor::new(md.as_bytes()), |chunk| {
+            largest_chunk = largest_chunk.max(chunk.len());
+        })
+        .unwrap();
+        // Bounded by the largest block, not the size of the input.
+        assert!(md.len() > 300_000);
+        assert_eq!(largest_chunk, paragraph.trim_end().len() + 1);
+
+        let mut kinds = Vec::new();
+        parse_streaming(Cursor::new(md.as_bytes()), Flavor::CommonMark, |block| {
+            kinds.push(crate::ast::node_type_name(&block.data.borrow().value));
+        })
+        .unwrap();
+        assert_eq!(kinds.len(), 10_001);
+        assert!(kinds[..10_000].iter().all(|&kind| kind == "paragraph"));
+        // The blank line inside the fence doesn't split the code block.
+        assert_eq!(kinds[10_000], "code_block");
+
+        let invalid = Cursor::new(&b"ok\n\n\xff\n"[..]);
+        let err = parse_streaming(invalid, Flavor::CommonMark, |_| {}).unwrap_err();
+        assert!(matches!(err, MkforgeError::NotUtf8));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_parse_config_from_toml_str() {
+        let config =
+            ParseConfig::from_toml_str("flavor = \"gfm\"\nfootnotes = true\nmath = \"dollars\"\n")
+                .unwrap();
+        assert!(matches!(config.flavor, Flavor::GitHub));
+        assert!(config.options.extension.footnotes);
+        assert!(config.options.extension.math_dollars);
+        // Untouched keys keep the flavor's defaults.
+        assert!(config.options.extension.table);
+        assert_eq!(config.file_path(), Path::new(""));
+        assert_eq!(config.with_file_path("a.md").file_path(), Path::new("a.md"));
+
+        let err = ParseConfig::from_toml_str("footnote = true\n")
+            .err()
+            .unwrap();
+        assert!(matches!(err, MkforgeError::Config(ref message) if message.contains("footnote")));
+        let err = ParseConfig::from_toml_str("flavor = \"rst\"\n")
+            .err()
+            .unwrap();
+        assert!(matches!(err, MkforgeError::Config(_)));
+        let err = ParseConfig::from_toml_str("math = \"latex\"\n")
+            .err()
+            .unwrap();
+        assert!(matches!(err, MkforgeError::Config(_)));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_parse_config_from_toml_file() {
+        let dir = temp_dir("config-toml");
+        let path = dir.join("mkforge.toml");
+        fs::write(&path, "smart_punctuation = true\n").unwrap();
+
+        let config = ParseConfig::from_toml_file(&path).unwrap();
+        assert!(config.options.parse.smart);
+        let missing = ParseConfig::from_toml_file(&dir.join("missing.toml"))
+            .err()
+            .unwrap();
+        assert!(matches!(missing, MkforgeError::ReadFile { .. }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
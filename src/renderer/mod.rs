@@ -0,0 +1,1449 @@
+/**
+ * ============================================================================
+ * Markdown Renderer Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Render parsed Markdown and the information extracted
+ * from it into output formats such as HTML.
+ * ============================================================================
+*/
+use crate::analysis::{Heading, Table, extract_headings, word_count};
+use crate::ast::{OwnedNode, from_owned_tree, to_owned_tree};
+use crate::parser::{Arena, Flavor};
+use comrak::nodes::{AstNode, ListType, NodeValue};
+use comrak::{ListStyleType, format_commonmark, format_html};
+#[cfg(feature = "highlight")]
+use comrak::{Plugins, format_html_with_plugins, plugins::syntect::SyntectAdapter};
+use std::io::{self, IsTerminal, Write};
+use std::ops::Range;
+
+/// Renders the AST to HTML using the options of the given flavor.
+///
+/// The flavor should match the one used for parsing, since options
+/// such as GFM's `github_pre_lang` and `tagfilter` change the output.
+pub fn to_html<'a>(root: &'a AstNode<'a>, flavor: Flavor) -> String {
+    let mut html = Vec::new();
+    // Writing into a Vec cannot fail.
+    format_html(root, &flavor.to_options(), &mut html).unwrap();
+    String::from_utf8_lossy(&html).into_owned()
+}
+
+/// HTML rendering behavior beyond what the flavor's options cover.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// Writes the content of inline code spans as is, without escaping
+    /// `<`, `>`, `&` and quotes, so entity examples like `` `&amp;` ``
+    /// show up as `&`. Code blocks are still escaped.
+    ///
+    /// This is an XSS hole for untrusted input: a code span such as
+    /// `` `</code><script>...` `` breaks out of its `<code>` element, and
+    /// neither the flavor's `tagfilter` nor the unsafe-HTML setting apply
+    /// to code spans. Only enable it for trusted documents, or pass the
+    /// output through `sanitize_html`.
+    pub raw_code_spans: bool,
+}
+
+/// Renders the AST to HTML like `to_html`, with extra `HtmlOptions`.
+pub fn to_html_with<'a>(
+    root: &'a AstNode<'a>,
+    flavor: Flavor,
+    html_options: &HtmlOptions,
+) -> String {
+    if !html_options.raw_code_spans {
+        return to_html(root, flavor);
+    }
+
+    comrak::create_formatter!(RawCodeSpans, {
+        NodeValue::Code(ref code) => |context, node, entering| {
+            if entering {
+                context.write_all(b"<code")?;
+                comrak::html::render_sourcepos(context, node)?;
+                context.write_all(b">")?;
+                context.write_all(code.literal.as_bytes())?;
+                context.write_all(b"</code>")?;
+            }
+        },
+    });
+
+    let mut html = Vec::new();
+    // Writing into a Vec cannot fail.
+    RawCodeSpans::format_document(root, &flavor.to_options(), &mut html).unwrap();
+    String::from_utf8_lossy(&html).into_owned()
+}
+
+/// Syntax highlighting styles for `to_html_highlighted`.
+///
+/// Every variant except `Classes` is one of syntect's bundled themes and
+/// emits inline `style` attributes. `Classes` emits CSS class names
+/// instead, for pages that ship their own stylesheet.
+#[cfg(feature = "highlight")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightTheme {
+    InspiredGitHub,
+    SolarizedDark,
+    SolarizedLight,
+    Base16OceanDark,
+    Base16OceanLight,
+    Base16EightiesDark,
+    Base16MochaDark,
+    Classes,
+}
+
+#[cfg(feature = "highlight")]
+impl HighlightTheme {
+    /// The name of the theme in syntect's default theme set.
+    fn theme_name(self) -> Option<&'static str> {
+        match self {
+            HighlightTheme::InspiredGitHub => Some("InspiredGitHub"),
+            HighlightTheme::SolarizedDark => Some("Solarized (dark)"),
+            HighlightTheme::SolarizedLight => Some("Solarized (light)"),
+            HighlightTheme::Base16OceanDark => Some("base16-ocean.dark"),
+            HighlightTheme::Base16OceanLight => Some("base16-ocean.light"),
+            HighlightTheme::Base16EightiesDark => Some("base16-eighties.dark"),
+            HighlightTheme::Base16MochaDark => Some("base16-mocha.dark"),
+            HighlightTheme::Classes => None,
+        }
+    }
+}
+
+/// Renders the AST to HTML with fenced code blocks syntax highlighted.
+///
+/// The highlighter is chosen from the first word of each fence's info
+/// string. Code blocks with an unknown or missing language are emitted
+/// as plain escaped text.
+#[cfg(feature = "highlight")]
+pub fn to_html_highlighted<'a>(
+    root: &'a AstNode<'a>,
+    flavor: Flavor,
+    theme: HighlightTheme,
+) -> String {
+    let adapter = SyntectAdapter::new(theme.theme_name());
+    let mut plugins = Plugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(&adapter);
+
+    let mut html = Vec::new();
+    // Writing into a Vec cannot fail.
+    format_html_with_plugins(root, &flavor.to_options(), &mut html, &plugins).unwrap();
+    String::from_utf8_lossy(&html).into_owned()
+}
+
+/// Renders the AST back to normalized CommonMark.
+///
+/// The output is canonical rather than faithful: whitespace is
+/// normalized, ordered lists are numbered sequentially from their start
+/// and every bullet list uses the same marker. That marker is the one
+/// the document's bullet lists use if they all agree, as they do after
+/// `transform::normalize_lists`, and the flavor's `list_style` otherwise.
+/// The flavor's extensions are respected, so e.g. GFM tables survive the
+/// round-trip.
+///
+/// Reference links, including `[text]` shortcuts, always come out as
+/// inline `[text](url)` links without their definitions. comrak resolves
+/// them while parsing and keeps no reference or definition nodes, so
+/// there is nothing left to rewrite beforehand.
+pub fn to_markdown<'a>(root: &'a AstNode<'a>, flavor: Flavor) -> String {
+    let mut options = flavor.to_options();
+    if let Some(style) = uniform_bullet(root) {
+        options.render.list_style = style;
+    }
+
+    let mut markdown = Vec::new();
+    // Writing into a Vec cannot fail.
+    format_commonmark(root, &options, &mut markdown).unwrap();
+    String::from_utf8_lossy(&markdown).into_owned()
+}
+
+/// Returns the bullet style shared by every bullet list, if there is one.
+fn uniform_bullet<'a>(root: &'a AstNode<'a>) -> Option<ListStyleType> {
+    let mut bullets = root
+        .descendants()
+        .filter_map(|node| match node.data.borrow().value {
+            NodeValue::List(ref list) if list.list_type == ListType::Bullet => {
+                Some(list.bullet_char)
+            }
+            _ => None,
+        });
+    let first = bullets.next()?;
+    if !bullets.all(|bullet| bullet == first) {
+        return None;
+    }
+    match first {
+        b'-' => Some(ListStyleType::Dash),
+        b'*' => Some(ListStyleType::Star),
+        b'+' => Some(ListStyleType::Plus),
+        _ => None,
+    }
+}
+
+/// A rendered document body together with its table of contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedPage {
+    pub html: String,
+    pub toc_html: String,
+    pub headings: Vec<Heading>,
+}
+
+/// Renders the AST to HTML along with an HTML table of contents.
+///
+/// Heading anchors are always rendered, even if the flavor leaves them
+/// off, and every TOC link points at its heading's `id`, including any
+/// prefix the flavor's `header_ids` option adds. `headings` holds the
+/// unprefixed slugs, as returned by `extract_headings`.
+pub fn render_page<'a>(root: &'a AstNode<'a>, flavor: Flavor) -> RenderedPage {
+    let mut options = flavor.to_options();
+    let prefix = options
+        .extension
+        .header_ids
+        .get_or_insert_with(String::new)
+        .clone();
+
+    let headings = extract_headings(root);
+    let anchors: Vec<Heading> = headings
+        .iter()
+        .map(|heading| Heading {
+            slug: format!("{}{}", prefix, heading.slug),
+            ..heading.clone()
+        })
+        .collect();
+
+    RenderedPage {
+        html: to_html(root, Flavor::Custom(options)),
+        toc_html: render_toc(&anchors, TocFormat::Html),
+        headings,
+    }
+}
+
+/// Renders the opening of a document as Markdown, for "read more" teasers.
+///
+/// Whole top-level blocks are kept while they fit within `max_words`,
+/// counted as by `word_count`. Only when the very first block is over
+/// the limit is it cut mid-way, after its last fitting word, with an
+/// ellipsis. The excerpt is rendered from the AST, so cutting never
+/// leaves emphasis or links unclosed. If the document has a top-level
+/// `<!-- more -->` comment, everything before it is the excerpt and
+/// `max_words` is ignored.
+pub fn excerpt<'a>(root: &'a AstNode<'a>, max_words: usize, flavor: Flavor) -> String {
+    let blocks: Vec<_> = root.children().collect();
+    let kept = match blocks.iter().position(|block| is_more_marker(block)) {
+        Some(marker) => blocks[..marker]
+            .iter()
+            .map(|block| to_owned_tree(block))
+            .collect(),
+        None => {
+            let mut kept = Vec::new();
+            let mut words = 0;
+            for block in blocks {
+                let block_words = word_count(block);
+                if words + block_words > max_words {
+                    if kept.is_empty() && max_words > 0 {
+                        let mut owned = to_owned_tree(block);
+                        let mut budget = max_words;
+                        truncate_words(&mut owned, &mut budget);
+                        kept.push(owned);
+                    }
+                    break;
+                }
+                words += block_words;
+                kept.push(to_owned_tree(block));
+            }
+            kept
+        }
+    };
+
+    let document = OwnedNode {
+        value: NodeValue::Document,
+        sourcepos: root.data.borrow().sourcepos,
+        children: kept,
+    };
+    let arena = Arena::new();
+    to_markdown(from_owned_tree(&document, &arena), flavor)
+}
+
+fn is_more_marker<'a>(node: &'a AstNode<'a>) -> bool {
+    match node.data.borrow().value {
+        NodeValue::HtmlBlock(ref html) => html
+            .literal
+            .trim()
+            .strip_prefix("<!--")
+            .and_then(|comment| comment.strip_suffix("-->"))
+            .is_some_and(|comment| comment.trim().eq_ignore_ascii_case("more")),
+        _ => false,
+    }
+}
+
+/// Cuts `node` down to its first `budget` words, dropping every node
+/// after the one that uses up the budget.
+fn truncate_words(node: &mut OwnedNode, budget: &mut usize) {
+    match node.value {
+        NodeValue::Text(ref mut text) => cut_after_words(text, budget, "…"),
+        NodeValue::Code(ref mut code) => cut_after_words(&mut code.literal, budget, ""),
+        NodeValue::CodeBlock(ref mut block) => cut_after_words(&mut block.literal, budget, ""),
+        // Alt text isn't counted, so keep the image whole.
+        NodeValue::Image(_) => return,
+        _ => {}
+    }
+
+    let mut fitting = 0;
+    for child in &mut node.children {
+        if *budget == 0 {
+            break;
+        }
+        truncate_words(child, budget);
+        fitting += 1;
+    }
+    node.children.truncate(fitting);
+}
+
+/// Truncates `text` after its `budget`th word, appending `marker` if
+/// anything was cut, and takes the words it keeps out of the budget.
+fn cut_after_words(text: &mut String, budget: &mut usize, marker: &str) {
+    let words = text.split_whitespace().count();
+    if words <= *budget {
+        *budget -= words;
+        return;
+    }
+
+    let mut seen = 0;
+    let mut end = 0;
+    let mut in_word = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if in_word && seen == *budget {
+                break;
+            }
+            in_word = false;
+        } else {
+            if !in_word {
+                seen += 1;
+                in_word = true;
+            }
+            end = i + c.len_utf8();
+        }
+    }
+    text.truncate(end);
+    text.push_str(marker);
+    *budget = 0;
+}
+
+/// Renders the AST to plain text with all formatting stripped.
+///
+/// Emphasis markup is dropped but its words are kept, links keep their
+/// visible text, images are replaced by their alt text and every block
+/// (paragraph, heading, list item, ...) goes on its own line. Raw HTML
+/// is dropped, as with `HtmlPolicy::Drop`.
+pub fn to_plain_text<'a>(root: &'a AstNode<'a>) -> String {
+    to_plain_text_with(root, HtmlPolicy::Drop)
+}
+
+/// How `to_plain_text_with` handles raw HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlPolicy {
+    /// Leaves raw HTML out. Markdown between inline tags, as in
+    /// `<em>hi</em>`, is still kept, but the text inside an HTML block is lost.
+    Drop,
+    /// Keeps the text of raw HTML with its tags stripped, the way
+    /// `sanitize_html` strips tags with an empty allowlist. Good enough
+    /// for search indexing, but entities are not decoded and a stray `<`
+    /// comes out escaped as `&lt;`.
+    KeepText,
+    /// Keeps raw HTML exactly as written.
+    KeepRaw,
+}
+
+/// Renders the AST to plain text like `to_plain_text`, handling raw HTML
+/// blocks and inline HTML according to `html`.
+pub fn to_plain_text_with<'a>(root: &'a AstNode<'a>, html: HtmlPolicy) -> String {
+    let mut text = String::new();
+    write_plain_text(root, html, &mut text);
+    text.truncate(text.trim_end().len());
+    text
+}
+
+fn write_plain_text<'a>(node: &'a AstNode<'a>, html: HtmlPolicy, out: &mut String) {
+    let value = &node.data.borrow().value;
+    match value {
+        NodeValue::HtmlBlock(block) => push_html(block.literal.trim_end_matches('\n'), html, out),
+        NodeValue::HtmlInline(inline) => push_html(inline, html, out),
+        NodeValue::Text(text) => out.push_str(text),
+        NodeValue::Code(code) => out.push_str(&code.literal),
+        NodeValue::Math(math) => out.push_str(&math.literal),
+        NodeValue::CodeBlock(block) => out.push_str(&block.literal),
+        NodeValue::SoftBreak => out.push(' '),
+        NodeValue::LineBreak => out.push('\n'),
+        NodeValue::FrontMatter(_) => {}
+        // Separate the cells of a table row with tabs.
+        NodeValue::TableCell if !out.is_empty() && !out.ends_with(['\n', '\t']) => out.push('\t'),
+        _ => {}
+    }
+
+    for child in node.children() {
+        write_plain_text(child, html, out);
+    }
+
+    let ends_line = value.block() && !matches!(value, NodeValue::TableCell);
+    if ends_line && !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn push_html(raw: &str, html: HtmlPolicy, out: &mut String) {
+    match html {
+        HtmlPolicy::Drop => {}
+        HtmlPolicy::KeepText => out.push_str(sanitize_html(raw, &[]).trim_matches('\n')),
+        HtmlPolicy::KeepRaw => out.push_str(raw),
+    }
+}
+
+/// Whether `to_ansi` styles its output with escape codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Always,
+    Never,
+    /// Styles output only when standard output is a terminal.
+    Auto,
+}
+
+impl ColorMode {
+    fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// Renders the AST for display in a terminal.
+///
+/// Headings are bold and underlined, emphasis is italic, strong text is
+/// bold, links are underlined and code blocks get a dark background.
+/// Paragraphs and list items are wrapped to `width` columns, counting
+/// characters, with list items indented under their `•` or number. Code
+/// blocks are never wrapped. Without color, the same layout is produced
+/// as plain text.
+pub fn to_ansi<'a>(root: &'a AstNode<'a>, width: usize, color: ColorMode) -> String {
+    let painter = Painter {
+        enabled: color.enabled(),
+    };
+    let mut out = ansi_block(root, width.max(1), painter).join("\n");
+    out.push('\n');
+    out
+}
+
+#[derive(Clone, Copy)]
+struct Painter {
+    enabled: bool,
+}
+
+impl Painter {
+    /// Wraps `text` in the SGR codes `on` and `off`.
+    fn paint(self, text: &str, on: &str, off: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[{}m", on, text, off)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Renders a block into lines of at most `width` visible characters.
+fn ansi_block<'a>(node: &'a AstNode<'a>, width: usize, painter: Painter) -> Vec<String> {
+    match node.data.borrow().value {
+        NodeValue::Document => ansi_children(node, width, painter, true),
+        NodeValue::Paragraph => wrap_ansi(&ansi_inline(node, painter), width),
+        NodeValue::Heading(_) => wrap_ansi(&ansi_inline(node, painter), width)
+            .into_iter()
+            .map(|line| painter.paint(&line, "1;4", "0"))
+            .collect(),
+        NodeValue::CodeBlock(ref block) => block
+            .literal
+            .lines()
+            .map(|line| {
+                let line = replace_controls(line);
+                let padded = format!(" {:<1$} ", line, width.saturating_sub(2));
+                painter.paint(&padded, "48;5;236", "49")
+            })
+            .collect(),
+        NodeValue::BlockQuote => ansi_children(node, width.saturating_sub(2).max(1), painter, true)
+            .into_iter()
+            .map(|line| format!("│ {}", line).trim_end().to_string())
+            .collect(),
+        NodeValue::List(ref list) => {
+            let mut lines = Vec::new();
+            for (i, item) in node.children().enumerate() {
+                let marker = match list.list_type {
+                    ListType::Bullet => "•".to_string(),
+                    ListType::Ordered => format!("{}.", list.start + i),
+                };
+                let indent = marker.chars().count() + 1;
+                let item_lines = ansi_children(
+                    item,
+                    width.saturating_sub(indent).max(1),
+                    painter,
+                    !list.tight,
+                );
+                if !list.tight && i > 0 {
+                    lines.push(String::new());
+                }
+                for (j, line) in item_lines.into_iter().enumerate() {
+                    let prefix = if j == 0 {
+                        format!("{} ", marker)
+                    } else {
+                        " ".repeat(indent)
+                    };
+                    lines.push(if line.is_empty() {
+                        line
+                    } else {
+                        prefix + &line
+                    });
+                }
+            }
+            lines
+        }
+        NodeValue::ThematicBreak => vec!["─".repeat(width)],
+        NodeValue::HtmlBlock(_) | NodeValue::FrontMatter(_) => Vec::new(),
+        _ => to_plain_text(node).lines().map(replace_controls).collect(),
+    }
+}
+
+/// Renders the children of a container, optionally separated by blank lines.
+fn ansi_children<'a>(
+    node: &'a AstNode<'a>,
+    width: usize,
+    painter: Painter,
+    spaced: bool,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for child in node.children() {
+        let block = ansi_block(child, width, painter);
+        if block.is_empty() {
+            continue;
+        }
+        if spaced && !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(block);
+    }
+    lines
+}
+
+/// Renders inline content on one line, with hard breaks as `\n`.
+fn ansi_inline<'a>(node: &'a AstNode<'a>, painter: Painter) -> String {
+    let children = || {
+        node.children()
+            .map(|child| ansi_inline(child, painter))
+            .collect::<String>()
+    };
+    match node.data.borrow().value {
+        NodeValue::Text(ref text) => replace_controls(text),
+        NodeValue::Code(ref code) => painter.paint(&replace_controls(&code.literal), "36", "39"),
+        NodeValue::SoftBreak => " ".to_string(),
+        NodeValue::LineBreak => "\n".to_string(),
+        NodeValue::Emph => painter.paint(&children(), "3", "23"),
+        NodeValue::Strong => painter.paint(&children(), "1", "22"),
+        NodeValue::Strikethrough => painter.paint(&children(), "9", "29"),
+        NodeValue::Link(_) | NodeValue::WikiLink(_) => painter.paint(&children(), "4", "24"),
+        NodeValue::HtmlInline(_) => String::new(),
+        _ => children(),
+    }
+}
+
+/// Greedily wraps `text` at spaces, measuring width without escape codes.
+/// A word longer than `width` gets a line of its own.
+fn wrap_ansi(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for hard_line in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in hard_line.split(' ').filter(|word| !word.is_empty()) {
+            let word_width = visible_width(word);
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Replaces control characters other than `\n` and `\t` with U+FFFD.
+///
+/// Document text must not reach the terminal as escape sequences, which
+/// could retitle the window or clear the screen, and only the renderer's
+/// own SGR codes may contain ESC for `visible_width` to measure correctly.
+fn replace_controls(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_control() && c != '\n' && c != '\t' {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// Output formats for a rendered table of contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TocFormat {
+    Markdown,
+    Html,
+}
+
+/// Renders headings as a nested list of links to their anchors.
+///
+/// Each heading is nested one level under the closest preceding heading
+/// with a smaller level, so skipped levels (h1 directly to h3) only
+/// indent by one.
+pub fn render_toc(headings: &[Heading], format: TocFormat) -> String {
+    let depths = toc_depths(headings);
+    match format {
+        TocFormat::Markdown => render_toc_markdown(headings, &depths),
+        TocFormat::Html => render_toc_html(headings, &depths),
+    }
+}
+
+/// Computes the nesting depth of each heading in the TOC.
+/// A heading is never more than one level deeper than the one before it.
+fn toc_depths(headings: &[Heading]) -> Vec<usize> {
+    let mut parents: Vec<u8> = Vec::new();
+    headings
+        .iter()
+        .map(|heading| {
+            while parents.last().is_some_and(|&level| level >= heading.level) {
+                parents.pop();
+            }
+            let depth = parents.len();
+            parents.push(heading.level);
+            depth
+        })
+        .collect()
+}
+
+fn render_toc_markdown(headings: &[Heading], depths: &[usize]) -> String {
+    let mut out = String::new();
+    for (heading, &depth) in headings.iter().zip(depths) {
+        let text = heading.text.replace('[', "\\[").replace(']', "\\]");
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&format!("- [{}](#{})\n", text, heading.slug));
+    }
+    out
+}
+
+fn render_toc_html(headings: &[Heading], depths: &[usize]) -> String {
+    if headings.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("<ul>\n");
+    let mut open_lists = 1;
+    for (i, (heading, &depth)) in headings.iter().zip(depths).enumerate() {
+        if i > 0 {
+            if depth + 1 > open_lists {
+                // Nest a new list inside the still-open previous item.
+                out.push_str("\n<ul>\n");
+                open_lists += 1;
+            } else {
+                out.push_str("</li>\n");
+                while open_lists > depth + 1 {
+                    out.push_str("</ul>\n</li>\n");
+                    open_lists -= 1;
+                }
+            }
+        }
+        out.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            escape_html(&heading.slug),
+            escape_html(&heading.text)
+        ));
+    }
+
+    out.push_str("</li>\n");
+    while open_lists > 1 {
+        out.push_str("</ul>\n</li>\n");
+        open_lists -= 1;
+    }
+    out.push_str("</ul>\n");
+    out
+}
+
+/// Renders a table as RFC 4180 CSV, starting with its header row.
+///
+/// Records end in CRLF. Fields containing a comma, a double quote or a
+/// line break are quoted, with embedded quotes doubled. A table without
+/// body rows still produces its header line; only a table without any
+/// columns produces an empty string.
+pub fn table_to_csv(table: &Table) -> String {
+    if table.headers.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    for record in std::iter::once(&table.headers).chain(&table.rows) {
+        let fields: Vec<_> = record.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Strips every HTML tag whose name isn't in `allowed_tags`.
+///
+/// This is a finer-grained alternative to GFM's fixed `tagfilter`, for
+/// rendered output that may contain raw HTML. Tag names match
+/// case-insensitively. Disallowed tags are removed but the text between
+/// them is kept, except inside `<script>` and `<style>`, whose contents
+/// are dropped too. Comments, doctypes and processing instructions are
+/// always removed. A `<` that doesn't start a tag is escaped as `&lt;`,
+/// and a tag that is never closed is dropped with everything after it.
+///
+/// Allowed tags keep their attributes, minus every `on*` event handler
+/// and any URL attribute such as `href` or `src` whose scheme isn't
+/// `http`, `https`, `mailto` or `tel`, so `javascript:` links are
+/// dropped. Other attributes, including `style`, are kept as written;
+/// only allow tags whose remaining attributes you trust.
+pub fn sanitize_html(html: &str, allowed_tags: &[&str]) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    // The raw text element whose contents are being dropped.
+    let mut dropping: Option<String> = None;
+
+    while let Some(start) = rest.find('<') {
+        let (text, markup) = rest.split_at(start);
+        if dropping.is_none() {
+            out.push_str(text);
+        }
+
+        let Some(tag) = scan_tag(markup) else {
+            // A browser would finish an unterminated tag at whatever `>`
+            // follows the output, so it goes along with the rest.
+            if starts_tag(markup) {
+                return out;
+            }
+            // A lone `<` that doesn't start a tag is just text.
+            if dropping.is_none() {
+                out.push_str("&lt;");
+            }
+            rest = &markup[1..];
+            continue;
+        };
+
+        match dropping {
+            Some(ref name) if tag.closing && tag.name == *name => dropping = None,
+            Some(_) => {}
+            None if allowed_tags
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&tag.name)) =>
+            {
+                out.push_str(&markup[..tag.name_end]);
+                for attribute in &tag.attributes {
+                    if is_safe_attribute(attribute) {
+                        out.push_str(&markup[attribute.span.clone()]);
+                    }
+                }
+                out.push_str(&markup[tag.tail_start..tag.len]);
+            }
+            None if !tag.closing
+                && !tag.self_closing
+                && matches!(tag.name.as_str(), "script" | "style") =>
+            {
+                dropping = Some(tag.name);
+            }
+            None => {}
+        }
+        rest = &markup[tag.len..];
+    }
+
+    if dropping.is_none() {
+        out.push_str(rest);
+    }
+    out
+}
+
+/// Returns whether `markup`, which begins with `<`, opens a tag, comment
+/// or declaration, complete or not.
+fn starts_tag(markup: &str) -> bool {
+    let rest = &markup[1..];
+    let rest = rest.strip_prefix('/').unwrap_or(rest);
+    rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '!' || c == '?')
+}
+
+/// A tag found by `scan_tag`.
+struct Tag {
+    /// Length of the whole tag in bytes, including `<` and `>`.
+    len: usize,
+    /// The lowercased tag name. Empty for comments and declarations.
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    /// End of the tag name; the byte range before it is copied as is.
+    name_end: usize,
+    /// The attributes, whose spans together cover everything from
+    /// `name_end` to `tail_start`.
+    attributes: Vec<Attribute>,
+    /// Start of whatever follows the last attribute, such as `/>`.
+    tail_start: usize,
+}
+
+/// An attribute of a `Tag`.
+struct Attribute {
+    /// The lowercased attribute name.
+    name: String,
+    /// The raw value, without quotes. Character references are not decoded.
+    value: String,
+    /// Byte range of the attribute in the tag, including the whitespace
+    /// before it.
+    span: Range<usize>,
+}
+
+/// Scans the tag at the start of `markup`, which begins with `<`.
+/// Returns `None` if it isn't a complete tag.
+fn scan_tag(markup: &str) -> Option<Tag> {
+    let declaration = |end: &str, skip: usize| {
+        let len = markup[skip..]
+            .find(end)
+            .map_or(markup.len(), |i| skip + i + end.len());
+        Tag {
+            len,
+            name: String::new(),
+            closing: false,
+            self_closing: true,
+            name_end: len,
+            attributes: Vec::new(),
+            tail_start: len,
+        }
+    };
+    if markup.starts_with("<!--") {
+        return Some(declaration("-->", 4));
+    }
+    if markup.starts_with("<!") || markup.starts_with("<?") {
+        return Some(declaration(">", 2));
+    }
+
+    let closing = markup[1..].starts_with('/');
+    let name_start = if closing { 2 } else { 1 };
+    let name_len = markup[name_start..]
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(markup.len() - name_start);
+    let name = &markup[name_start..name_start + name_len];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    // Tokenize the attributes the way browsers do: quotes only open a
+    // value directly after `=`, anywhere else they are ordinary characters.
+    let name_end = name_start + name_len;
+    let bytes = markup.as_bytes();
+    let skip_spaces = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+    let mut attributes = Vec::new();
+    let mut i = name_end;
+    loop {
+        let span_start = i;
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        match bytes.get(i) {
+            None => return None,
+            Some(b'>') => {
+                return Some(Tag {
+                    len: i + 1,
+                    name: name.to_ascii_lowercase(),
+                    closing,
+                    self_closing: markup[..i].ends_with('/'),
+                    name_end,
+                    attributes,
+                    tail_start: span_start,
+                });
+            }
+            Some(_) => {}
+        }
+
+        // A leading `=` belongs to the attribute name.
+        let attribute_start = i;
+        i += 1;
+        while i < bytes.len()
+            && !matches!(bytes[i], b'/' | b'>' | b'=')
+            && !bytes[i].is_ascii_whitespace()
+        {
+            i += 1;
+        }
+        let attribute_name = markup[attribute_start..i].to_ascii_lowercase();
+
+        let mut value = String::new();
+        let after_name = skip_spaces(i);
+        if bytes.get(after_name) == Some(&b'=') {
+            let value_start = skip_spaces(after_name + 1);
+            i = match bytes.get(value_start) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let len = markup[value_start + 1..].find(quote as char)?;
+                    value = markup[value_start + 1..value_start + 1 + len].to_string();
+                    value_start + len + 2
+                }
+                _ => {
+                    let len = markup[value_start..]
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(markup.len() - value_start);
+                    value = markup[value_start..value_start + len].to_string();
+                    value_start + len
+                }
+            };
+        }
+        attributes.push(Attribute {
+            name: attribute_name,
+            value,
+            span: span_start..i,
+        });
+    }
+}
+
+/// Returns whether `attribute` can be kept on an allowed tag.
+fn is_safe_attribute(attribute: &Attribute) -> bool {
+    const URL_ATTRIBUTES: &[&str] = &[
+        "action",
+        "background",
+        "cite",
+        "data",
+        "formaction",
+        "href",
+        "poster",
+        "src",
+        "xlink:href",
+    ];
+    if attribute.name.starts_with("on") {
+        return false;
+    }
+    !URL_ATTRIBUTES.contains(&attribute.name.as_str()) || is_safe_url(&attribute.value)
+}
+
+/// Returns whether `url` is relative or uses an allowed scheme.
+///
+/// Browsers decode character references in attribute values and ignore
+/// whitespace and control characters inside the scheme, so both are
+/// undone before looking at it, e.g. for `jav&#x09;ascript&colon;`.
+fn is_safe_url(url: &str) -> bool {
+    let decoded: String = decode_char_refs(url)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match decoded.find([':', '/', '?', '#']) {
+        Some(i) if decoded[i..].starts_with(':') => {
+            matches!(&decoded[..i], "http" | "https" | "mailto" | "tel")
+        }
+        _ => true,
+    }
+}
+
+/// Decodes numeric character references and the named ones that can
+/// spell out a URL scheme. Other named references are kept as written.
+fn decode_char_refs(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let (digits, radix) = match rest.strip_prefix('#') {
+            Some(hex) if hex.starts_with(['x', 'X']) => (&hex[1..], 16),
+            Some(decimal) => (decimal, 10),
+            None => {
+                let named = [("colon;", ':'), ("tab;", '\t'), ("newline;", '\n')]
+                    .into_iter()
+                    .find(|(name, _)| {
+                        rest.get(..name.len())
+                            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+                    });
+                match named {
+                    Some((name, c)) => {
+                        decoded.push(c);
+                        rest = &rest[name.len()..];
+                    }
+                    None => decoded.push('&'),
+                }
+                continue;
+            }
+        };
+        let len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        match u32::from_str_radix(&digits[..len], radix)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) if len > 0 => {
+                decoded.push(c);
+                // The terminating `;` is optional.
+                let end = digits[len..].strip_prefix(';').unwrap_or(&digits[len..]);
+                rest = end;
+            }
+            _ => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Alignment, extract_tables};
+    use crate::parser::{Arena, extract_ast_from_str};
+
+    #[test]
+    fn test_to_html() {
+        let arena = Arena::new();
+        let root = extract_ast_from_str("# Hi", Flavor::CommonMark, &arena);
+        assert_eq!(to_html(root, Flavor::CommonMark), "<h1>Hi</h1>\n");
+    }
+
+    #[test]
+    fn test_to_html_uses_flavor_options() {
+        let arena = Arena::new();
+        let md = "```rust\nfn main() {}\n```\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+        assert!(to_html(root, Flavor::GitHub).starts_with("<pre lang=\"rust\">"));
+        assert!(
+            to_html(root, Flavor::CommonMark).starts_with("<pre><code class=\"language-rust\">")
+        );
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_to_html_highlighted() {
+        let arena = Arena::new();
+        let md = "```rust\nfn main() {}\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let html = to_html_highlighted(root, Flavor::CommonMark, HighlightTheme::InspiredGitHub);
+        assert!(html.contains("<span style="));
+        let html = to_html_highlighted(root, Flavor::CommonMark, HighlightTheme::Classes);
+        assert!(html.contains("<span class="));
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn test_to_html_highlighted_unknown_language() {
+        let arena = Arena::new();
+        let md = "```no-such-lang\na < b\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let html = to_html_highlighted(root, Flavor::CommonMark, HighlightTheme::Classes);
+        assert!(html.contains("a &lt; b"));
+    }
+
+    #[test]
+    fn test_to_markdown_normalizes_bullets() {
+        let arena = Arena::new();
+        let md = "* one\n*   two\n\n+ three\n+    four\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        // The two lists stay separate, but share a single marker.
+        assert_eq!(
+            to_markdown(root, Flavor::CommonMark),
+            "- one\n- two\n\n<!-- end list -->\n\n- three\n- four\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_keeps_uniform_bullets() {
+        let arena = Arena::new();
+        let root = extract_ast_from_str("* one\n* two\n", Flavor::CommonMark, &arena);
+        assert_eq!(to_markdown(root, Flavor::CommonMark), "* one\n* two\n");
+    }
+
+    #[test]
+    fn test_to_markdown_inlines_reference_links() {
+        let arena = Arena::new();
+        let md =
+            "See [the docs][docs] and [home].\n\n[docs]: https://a.example \"Docs\"\n[home]: /\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+        assert_eq!(
+            to_markdown(root, Flavor::CommonMark),
+            "See [the docs](https://a.example \"Docs\") and [home](/).\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_keeps_gfm_tables() {
+        let arena = Arena::new();
+        let md = "|a|b|\n|-|-|\n|1|2|\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+        assert_eq!(
+            to_markdown(root, Flavor::GitHub),
+            "| a | b |\n| --- | --- |\n| 1 | 2 |\n"
+        );
+    }
+
+    #[test]
+    fn test_to_plain_text() {
+        let arena = Arena::new();
+        let root =
+            extract_ast_from_str("**bold** and [link](http://x)", Flavor::CommonMark, &arena);
+        assert_eq!(to_plain_text(root), "bold and link");
+    }
+
+    #[test]
+    fn test_to_plain_text_blocks() {
+        let arena = Arena::new();
+        let md = "# Title\n\nSome *soft*\nwrapped text.\n\n- one\n- two ![an image](a.png)\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+        assert_eq!(
+            to_plain_text(root),
+            "Title\nSome soft wrapped text.\none\ntwo an image"
+        );
+    }
+
+    fn heading(level: u8, text: &str, slug: &str) -> Heading {
+        Heading {
+            level,
+            text: text.to_string(),
+            slug: slug.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sample_headings() -> Vec<Heading> {
+        vec![
+            heading(1, "Guide", "guide"),
+            heading(2, "Install", "install"),
+            heading(3, "Linux", "linux"),
+        ]
+    }
+
+    #[test]
+    fn test_render_toc_markdown() {
+        assert_eq!(
+            render_toc(&sample_headings(), TocFormat::Markdown),
+            "- [Guide](#guide)\n  - [Install](#install)\n    - [Linux](#linux)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_toc_html() {
+        assert_eq!(
+            render_toc(&sample_headings(), TocFormat::Html),
+            "<ul>\n\
+             <li><a href=\"#guide\">Guide</a>\n<ul>\n\
+             <li><a href=\"#install\">Install</a>\n<ul>\n\
+             <li><a href=\"#linux\">Linux</a></li>\n\
+             </ul>\n</li>\n\
+             </ul>\n</li>\n\
+             </ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_toc_skipped_levels() {
+        let headings = vec![
+            heading(1, "Top", "top"),
+            heading(3, "Deep", "deep"),
+            heading(2, "Middle", "middle"),
+            heading(1, "Next", "next"),
+        ];
+        assert_eq!(
+            render_toc(&headings, TocFormat::Markdown),
+            "- [Top](#top)\n  - [Deep](#deep)\n  - [Middle](#middle)\n- [Next](#next)\n"
+        );
+        assert_eq!(
+            render_toc(&headings, TocFormat::Html),
+            "<ul>\n\
+             <li><a href=\"#top\">Top</a>\n<ul>\n\
+             <li><a href=\"#deep\">Deep</a></li>\n\
+             <li><a href=\"#middle\">Middle</a></li>\n\
+             </ul>\n</li>\n\
+             <li><a href=\"#next\">Next</a></li>\n\
+             </ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_table_to_csv() {
+        let table = Table {
+            headers: vec!["Name".to_string(), "Quote".to_string()],
+            rows: vec![vec!["Doe, Jane".to_string(), "She said \"hi\"".to_string()]],
+            alignments: vec![Alignment::None, Alignment::None],
+        };
+        assert_eq!(
+            table_to_csv(&table),
+            "Name,Quote\r\n\"Doe, Jane\",\"She said \"\"hi\"\"\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_table_to_csv_empty() {
+        let arena = Arena::new();
+        let root = extract_ast_from_str("| a | b |\n|---|---|\n", Flavor::GitHub, &arena);
+        let tables = extract_tables(root);
+        assert_eq!(table_to_csv(&tables[0]), "a,b\r\n");
+    }
+
+    #[test]
+    fn test_render_page() {
+        let arena = Arena::new();
+        let md = "# Guide\n\n## Install\n\nText.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        let page = render_page(root, Flavor::CommonMark);
+        assert_eq!(page.headings.len(), 2);
+        assert!(page.toc_html.contains("<a href=\"#install\">Install</a>"));
+        assert!(page.html.contains("id=\"install\""));
+
+        let mut options = Flavor::CommonMark.to_options();
+        options.extension.header_ids = Some("doc-".to_string());
+        let page = render_page(root, Flavor::Custom(options));
+        assert_eq!(page.headings[1].slug, "install");
+        assert!(
+            page.toc_html
+                .contains("<a href=\"#doc-install\">Install</a>")
+        );
+        assert!(page.html.contains("id=\"doc-install\""));
+    }
+
+    #[test]
+    fn test_to_html_raw_code_spans() {
+        let arena = Arena::new();
+        let root = extract_ast_from_str("Use `<b>` or `&amp;`.", Flavor::CommonMark, &arena);
+
+        let escaped = "<p>Use <code>&lt;b&gt;</code> or <code>&amp;amp;</code>.</p>\n";
+        assert_eq!(to_html(root, Flavor::CommonMark), escaped);
+        assert_eq!(
+            to_html_with(root, Flavor::CommonMark, &HtmlOptions::default()),
+            escaped
+        );
+
+        let raw = HtmlOptions {
+            raw_code_spans: true,
+        };
+        assert_eq!(
+            to_html_with(root, Flavor::CommonMark, &raw),
+            "<p>Use <code><b></code> or <code>&amp;</code>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_word_limit() {
+        let arena = Arena::new();
+        let md = "# Intro\n\nOne *two* three.\n\nFour five six seven.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        // The second paragraph would go over the limit, so stop before it.
+        assert_eq!(
+            excerpt(root, 5, Flavor::CommonMark),
+            "# Intro\n\nOne *two* three.\n"
+        );
+        assert_eq!(excerpt(root, 0, Flavor::CommonMark), "");
+
+        // A long first block is cut inside, with its emphasis closed.
+        let md = "Some **very long bold paragraph** here.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+        assert_eq!(
+            excerpt(root, 3, Flavor::CommonMark),
+            "Some **very long…**\n"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_more_marker() {
+        let arena = Arena::new();
+        let md =
+            "First paragraph with many words in it.\n\nSecond one.\n\n<!-- more -->\n\nHidden.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        // The marker wins over the word limit in both directions.
+        assert_eq!(
+            excerpt(root, 2, Flavor::CommonMark),
+            "First paragraph with many words in it.\n\nSecond one.\n"
+        );
+        assert_eq!(
+            excerpt(root, 100, Flavor::CommonMark),
+            "First paragraph with many words in it.\n\nSecond one.\n"
+        );
+    }
+
+    #[test]
+    fn test_to_plain_text_html_policy() {
+        let arena = Arena::new();
+        let md = "Say <em>hi</em> now.\n\n<div class=\"note\">\nInside <b>div</b>\n</div>\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(to_plain_text(root), "Say hi now.");
+        assert_eq!(to_plain_text_with(root, HtmlPolicy::Drop), "Say hi now.");
+        assert_eq!(
+            to_plain_text_with(root, HtmlPolicy::KeepText),
+            "Say hi now.\nInside div"
+        );
+        assert_eq!(
+            to_plain_text_with(root, HtmlPolicy::KeepRaw),
+            "Say <em>hi</em> now.\n<div class=\"note\">\nInside <b>div</b>\n</div>"
+        );
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        let arena = Arena::new();
+        let md = "# Title\n\nSome *emphasized* and **bold** words that wrap.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            to_ansi(root, 20, ColorMode::Always),
+            "\x1b[1;4mTitle\x1b[0m\n\
+             \n\
+             Some \x1b[3memphasized\x1b[23m and\n\
+             \x1b[1mbold\x1b[22m words that\n\
+             wrap.\n"
+        );
+        assert_eq!(
+            to_ansi(root, 20, ColorMode::Never),
+            "Title\n\nSome emphasized and\nbold words that\nwrap.\n"
+        );
+    }
+
+    #[test]
+    fn test_to_ansi_lists_and_code() {
+        let arena = Arena::new();
+        let md = "- first item here\n- second\n\n1. one\n\n```\nlet x;\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            to_ansi(root, 12, ColorMode::Never),
+            "• first item\n  here\n• second\n\n1. one\n\n let x;     \n"
+        );
+        assert!(
+            to_ansi(root, 12, ColorMode::Always).contains("\x1b[48;5;236m let x;     \x1b[49m")
+        );
+    }
+
+    #[test]
+    fn test_to_ansi_replaces_control_characters() {
+        let arena = Arena::new();
+        let md = "hi \x1b]0;pwned\x07 \x1b[2J `\x1b[31m`\n\n```\n\x1b[2Jcode\u{9b}\n```\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        assert_eq!(
+            to_ansi(root, 40, ColorMode::Never),
+            "hi \u{fffd}]0;pwned\u{fffd} \u{fffd}[2J \u{fffd}[31m\n\n \u{fffd}[2Jcode\u{fffd}"
+                .to_string()
+                + &" ".repeat(30)
+                + "\n"
+        );
+        let colored = to_ansi(root, 40, ColorMode::Always);
+        assert!(!colored.contains("\x1b]"));
+        assert!(!colored.contains("\x1b[2J"));
+        assert!(!colored.contains('\x07'));
+    }
+
+    #[test]
+    fn test_sanitize_html() {
+        let html = "<p>Hi <b class=\"x\">there</b><script>alert(1)</script>\
+                    <img src=\"a.png\" alt=\"a > b\"/><BR/></p><!-- note -->";
+        assert_eq!(sanitize_html(html, &["b"]), "Hi <b class=\"x\">there</b>");
+        assert_eq!(
+            sanitize_html(html, &["p", "b", "img", "br"]),
+            "<p>Hi <b class=\"x\">there</b><img src=\"a.png\" alt=\"a > b\"/><BR/></p>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_keeps_text() {
+        assert_eq!(
+            sanitize_html("<div onclick=\"x()\">1 < 2 <em>and</em> more</div>", &[]),
+            "1 &lt; 2 and more"
+        );
+        assert_eq!(sanitize_html("<style>p {}</style>after", &[]), "after");
+    }
+
+    #[test]
+    fn test_sanitize_html_quotes_only_open_values() {
+        // The quote inside the unquoted value must not hide the `<img>`.
+        assert_eq!(
+            sanitize_html("<b title=a'b><img src=x onerror=alert(1)>'>", &["b"]),
+            "<b title=a'b>'>"
+        );
+        assert_eq!(
+            sanitize_html("<b title = \"a>b\">x</b>", &["b"]),
+            "<b title = \"a>b\">x</b>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_drops_unterminated_tags() {
+        let allowed = ["b", "p"];
+        assert_eq!(
+            sanitize_html("<b>hi</b><img src=x onerror=alert(1)//", &allowed),
+            "<b>hi</b>"
+        );
+        assert_eq!(sanitize_html("<p>a</p><script", &allowed), "<p>a</p>");
+        assert_eq!(
+            sanitize_html("x <img src=\"x\" onerror=\"alert(1)\" a=\"", &allowed),
+            "x "
+        );
+        assert_eq!(sanitize_html("<b title='a>", &allowed), "");
+        assert_eq!(
+            sanitize_html("a <3 and < b", &allowed),
+            "a &lt;3 and &lt; b"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_html_strips_unsafe_attributes() {
+        assert_eq!(
+            sanitize_html("<b class=x OnMouseOver=\"alert(1)\" id='y'>hi</b>", &["b"]),
+            "<b class=x id='y'>hi</b>"
+        );
+        let links = "<a href=\"javascript:alert(1)\">1</a><a href=' JaVa\tScript:x'>2</a>\
+                     <a href=\"jav&#x61;script&colon;x\">3</a><a href=\"https://a.example\">4</a>\
+                     <a href=\"/docs?q=a:b\">5</a>";
+        assert_eq!(
+            sanitize_html(links, &["a"]),
+            "<a>1</a><a>2</a><a>3</a><a href=\"https://a.example\">4</a><a href=\"/docs?q=a:b\">5</a>"
+        );
+    }
+}
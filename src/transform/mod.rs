@@ -0,0 +1,1160 @@
+/**
+ * ============================================================================
+ * AST Transform Module
+ * Copyright (c) 2025 Viresh Mittal
+ *
+ * Rewrite a parsed Markdown AST in place before it is
+ * rendered, e.g. to adjust URLs or drop unwanted nodes.
+ * ============================================================================
+*/
+use crate::analysis::{extract_headings, has_scheme};
+use crate::ast::{from_owned_tree, to_owned_tree};
+use crate::error::MkforgeError;
+use crate::lint::fence_marker;
+use crate::parser::{Arena, Flavor, ParseConfig, extract_ast_from_str, parse};
+use crate::renderer::{TocFormat, render_toc, to_plain_text};
+use comrak::nodes::{AstNode, ListType, NodeLink, NodeList, NodeValue};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Prefixes every relative image destination with `base`.
+///
+/// Only relative paths such as `img/a.png` or `./img/a.png` change.
+/// URLs with a scheme (`https:`, `data:`, ...), root-absolute `/paths`,
+/// which include protocol-relative `//host` URLs, `#fragments` and empty
+/// destinations are left untouched. Nodes are updated through their
+/// `RefCell`s, so the tree itself is not rebuilt.
+pub fn rewrite_image_urls<'a>(root: &'a AstNode<'a>, base: &str) {
+    let base = base.trim_end_matches('/');
+    for node in root.descendants() {
+        if let NodeValue::Image(ref mut image) = node.data.borrow_mut().value {
+            if image.url.is_empty() || has_scheme(&image.url) || image.url.starts_with(['/', '#']) {
+                continue;
+            }
+            let path = image.url.trim_start_matches("./");
+            image.url = format!("{}/{}", base, path);
+        }
+    }
+}
+
+/// Removes raw HTML nodes that consist of nothing but an HTML comment.
+///
+/// Both block (`<!-- TODO -->` on its own lines) and inline comments are
+/// detached from the tree. HTML that mixes a comment with real markup is
+/// kept as is, since removing part of it could corrupt the output.
+pub fn strip_comments<'a>(root: &'a AstNode<'a>) {
+    // Collect first: detaching while iterating would cut the traversal short.
+    let comments: Vec<_> = root
+        .descendants()
+        .filter(|node| match node.data.borrow().value {
+            NodeValue::HtmlBlock(ref html) => is_comment(&html.literal),
+            NodeValue::HtmlInline(ref html) => is_comment(html),
+            _ => false,
+        })
+        .collect();
+
+    for node in comments {
+        node.detach();
+    }
+}
+
+/// Prefixes every heading with its outline number, e.g. "1.2 Scope".
+///
+/// Numbers follow the same nesting as the table of contents: a heading
+/// is numbered under the closest preceding shallower heading, and a
+/// shallower heading resets the counters below it. The number is
+/// prepended to the heading's first text node, so it ends up inside
+/// any emphasis that starts the heading. Headings without text are
+/// still counted but left unchanged.
+pub fn number_headings<'a>(root: &'a AstNode<'a>) {
+    // (level, count) for each open nesting depth.
+    let mut counters: Vec<(u8, usize)> = Vec::new();
+
+    for node in root.descendants() {
+        let level = match node.data.borrow().value {
+            NodeValue::Heading(ref heading) => heading.level,
+            _ => continue,
+        };
+
+        while counters.last().is_some_and(|&(open, _)| open > level) {
+            counters.pop();
+        }
+        match counters.last_mut() {
+            Some((open, count)) if *open == level => *count += 1,
+            _ => counters.push((level, 1)),
+        }
+
+        let number = counters
+            .iter()
+            .map(|(_, count)| count.to_string())
+            .collect::<Vec<_>>()
+            .join(".");
+        let first_text = node
+            .descendants()
+            .find(|child| matches!(child.data.borrow().value, NodeValue::Text(_)));
+        if let Some(text_node) = first_text
+            && let NodeValue::Text(ref mut text) = text_node.data.borrow_mut().value
+        {
+            text.insert_str(0, &format!("{} ", number));
+        }
+    }
+}
+
+/// Shifts every heading's level by `delta`, e.g. `1` turns h1 into h2.
+///
+/// Negative deltas promote headings instead. Levels are clamped to the
+/// valid 1 to 6 range, so an h6 shifted by `1` stays an h6 and an h1
+/// shifted by `-1` stays an h1. Clamping can merge levels that were
+/// distinct before, which shifting back will not undo.
+pub fn shift_heading_levels<'a>(root: &'a AstNode<'a>, delta: i8) {
+    for node in root.descendants() {
+        if let NodeValue::Heading(ref mut heading) = node.data.borrow_mut().value {
+            heading.level = (i16::from(heading.level) + i16::from(delta)).clamp(1, 6) as u8;
+        }
+    }
+}
+
+/// Replaces emphasis, strong emphasis and strikethrough with their contents.
+///
+/// The inline children are moved up into the emphasis node's place, so
+/// text and nested links survive and nested emphasis such as
+/// `**_x_**` flattens completely.
+pub fn flatten_emphasis<'a>(root: &'a AstNode<'a>) {
+    let emphasis: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                node.data.borrow().value,
+                NodeValue::Emph | NodeValue::Strong | NodeValue::Strikethrough
+            )
+        })
+        .collect();
+
+    for node in emphasis {
+        while let Some(child) = node.first_child() {
+            node.insert_before(child);
+        }
+        node.detach();
+    }
+}
+
+/// Replaces `[[TOC]]` or `[TOC]` placeholder paragraphs with a table of contents.
+///
+/// The TOC lists every heading in the document, including those before
+/// the placeholder, and is parsed with `flavor` into nodes allocated in
+/// `arena`, which must be the arena the document was parsed into.
+/// Placeholders are removed even when the document has no headings.
+pub fn inject_toc<'a>(root: &'a AstNode<'a>, flavor: Flavor, arena: &'a Arena<AstNode<'a>>) {
+    let placeholders: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(node.data.borrow().value, NodeValue::Paragraph)
+                && matches!(to_plain_text(node).as_str(), "[[TOC]]" | "[TOC]")
+        })
+        .collect();
+    if placeholders.is_empty() {
+        return;
+    }
+
+    let toc = render_toc(&extract_headings(root), TocFormat::Markdown);
+    for placeholder in placeholders {
+        let document = extract_ast_from_str(&toc, flavor.clone(), arena);
+        while let Some(child) = document.first_child() {
+            // Inserting moves the node out of the temporary document.
+            placeholder.insert_before(child);
+        }
+        placeholder.detach();
+    }
+}
+
+/// Replaces `{% include "path.md" %}` paragraphs with the referenced file.
+///
+/// Include paths are resolved relative to the directory of the file
+/// that contains them, and included files are parsed with `flavor` into
+/// `arena` and have their own includes resolved recursively. A file that
+/// ends up including itself is reported as `MkforgeError::IncludeCycle`,
+/// and nesting more than `max_depth` includes deep (so any include at all
+/// when `max_depth` is 0) as `MkforgeError::IncludeTooDeep`. A missing
+/// or unreadable include target is a `MkforgeError::ReadFile`.
+pub fn resolve_includes<'a>(
+    doc_path: &Path,
+    root: &'a AstNode<'a>,
+    flavor: Flavor,
+    max_depth: usize,
+    arena: &'a Arena<AstNode<'a>>,
+) -> Result<(), MkforgeError> {
+    let canonical = fs::canonicalize(doc_path).unwrap_or_else(|_| doc_path.to_path_buf());
+    let mut including = vec![canonical];
+    resolve_includes_in(doc_path, root, &flavor, max_depth, arena, &mut including)
+}
+
+/// Resolves the includes of one document; `including` holds the
+/// canonical paths of every file currently being expanded.
+fn resolve_includes_in<'a>(
+    doc_path: &Path,
+    root: &'a AstNode<'a>,
+    flavor: &Flavor,
+    max_depth: usize,
+    arena: &'a Arena<AstNode<'a>>,
+    including: &mut Vec<PathBuf>,
+) -> Result<(), MkforgeError> {
+    let directives: Vec<_> = root
+        .children()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .filter_map(|node| {
+            include_target(&to_plain_text(node)).map(|target| (node, target.to_string()))
+        })
+        .collect();
+
+    let dir = doc_path.parent().unwrap_or(Path::new(""));
+    for (directive, target) in directives {
+        let path = dir.join(target);
+        let canonical = fs::canonicalize(&path).map_err(|source| MkforgeError::ReadFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        if including.contains(&canonical) {
+            return Err(MkforgeError::IncludeCycle {
+                path: path.display().to_string(),
+            });
+        }
+        if including.len() > max_depth {
+            return Err(MkforgeError::IncludeTooDeep {
+                path: path.display().to_string(),
+                max_depth,
+            });
+        }
+
+        let included = parse(&ParseConfig::new(&path, flavor.clone()), arena)?;
+        including.push(canonical);
+        resolve_includes_in(&path, included.root, flavor, max_depth, arena, including)?;
+        including.pop();
+
+        while let Some(child) = included.root.first_child() {
+            directive.insert_before(child);
+        }
+        directive.detach();
+    }
+
+    Ok(())
+}
+
+/// Returns the quoted path of an `{% include "path" %}` directive.
+fn include_target(text: &str) -> Option<&str> {
+    let inner = text.trim().strip_prefix("{%")?.strip_suffix("%}")?.trim();
+    let quoted = inner.strip_prefix("include")?.trim_start();
+    quoted
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .or_else(|| {
+            quoted
+                .strip_prefix('\'')
+                .and_then(|rest| rest.strip_suffix('\''))
+        })
+}
+
+/// Returns whether `html` is exactly one comment, ignoring surrounding whitespace.
+fn is_comment(html: &str) -> bool {
+    html.trim()
+        .strip_prefix("<!--")
+        .and_then(|rest| rest.strip_suffix("-->"))
+        .is_some_and(|body| !body.contains("-->"))
+}
+
+/// Replaces `:name:` shortcodes in text with their emoji, e.g.
+/// `:rocket:` with 🚀.
+///
+/// Names are looked up in a bundled table of common GitHub shortcodes;
+/// unknown ones are left as typed. Only text nodes are touched, so
+/// shortcodes in code spans, code blocks, raw HTML and URLs survive.
+#[cfg(feature = "emoji")]
+pub fn replace_emoji_shortcodes<'a>(root: &'a AstNode<'a>) {
+    for node in root.descendants() {
+        if let NodeValue::Text(ref mut text) = node.data.borrow_mut().value
+            && text.contains(':')
+        {
+            *text = replace_shortcodes(text);
+        }
+    }
+}
+
+#[cfg(feature = "emoji")]
+fn replace_shortcodes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))
+            .unwrap_or(after.len());
+        let emoji = after[name_len..]
+            .starts_with(':')
+            .then(|| emoji_for(&after[..name_len]))
+            .flatten();
+        match emoji {
+            Some(emoji) => {
+                out.push_str(emoji);
+                rest = &after[name_len + 1..];
+            }
+            // The next colon may open a shortcode of its own.
+            None => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(feature = "emoji")]
+fn emoji_for(name: &str) -> Option<&'static str> {
+    EMOJI_SHORTCODES
+        .binary_search_by_key(&name, |&(shortcode, _)| shortcode)
+        .ok()
+        .map(|i| EMOJI_SHORTCODES[i].1)
+}
+
+/// Shortcode names and their emoji, sorted by name for binary search.
+#[cfg(feature = "emoji")]
+const EMOJI_SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("cat", "🐱"),
+    ("chart_with_upwards_trend", "📈"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("coffee", "☕"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("dog", "🐶"),
+    ("echo", "🔁"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("gear", "⚙️"),
+    ("gift", "🎁"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("muscle", "💪"),
+    ("no_entry", "⛔"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rainbow", "🌈"),
+    ("recycle", "♻️"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+/// Makes every bullet list use `bullet` and, with `renumber`, every
+/// ordered list count `1.`, `2.`, `3.`, ... whatever it started at.
+///
+/// Nested lists are normalized too. `bullet` should be `-`, `*` or `+`;
+/// any other character leaves bullets alone. When rendering with
+/// `renderer::to_markdown`, the normalized bullet is then used for
+/// the whole document.
+pub fn normalize_lists<'a>(root: &'a AstNode<'a>, bullet: char, renumber: bool) {
+    let bullet = matches!(bullet, '-' | '*' | '+').then_some(bullet as u8);
+    for list in root.descendants() {
+        match list.data.borrow_mut().value {
+            NodeValue::List(ref mut list) => normalize_list(list, bullet, renumber, 1),
+            _ => continue,
+        }
+        // Items carry their own copy of the list's marker and number.
+        for (i, item) in list.children().enumerate() {
+            if let NodeValue::Item(ref mut item) = item.data.borrow_mut().value {
+                normalize_list(item, bullet, renumber, i + 1);
+            }
+        }
+    }
+}
+
+fn normalize_list(list: &mut NodeList, bullet: Option<u8>, renumber: bool, number: usize) {
+    match list.list_type {
+        ListType::Bullet => {
+            if let Some(bullet) = bullet {
+                list.bullet_char = bullet;
+            }
+        }
+        ListType::Ordered if renumber => list.start = number,
+        ListType::Ordered => {}
+    }
+}
+
+/// Combines several documents into a new one, in order.
+///
+/// The result is a fresh document node in `arena` holding a deep copy of
+/// each input's top-level blocks, so the inputs are left untouched and
+/// later edits to either side don't show up in the other. Copies keep
+/// their original source positions, which therefore refer to the file
+/// each block came from. With `thematic_breaks`, a `---` break is put
+/// between consecutive documents.
+pub fn concat_documents<'a>(
+    docs: &[&'a AstNode<'a>],
+    thematic_breaks: bool,
+    arena: &'a Arena<AstNode<'a>>,
+) -> &'a AstNode<'a> {
+    let combined = arena.alloc(AstNode::from(NodeValue::Document));
+    for (i, doc) in docs.iter().enumerate() {
+        if thematic_breaks && i > 0 {
+            combined.append(arena.alloc(AstNode::from(NodeValue::ThematicBreak)));
+        }
+        for block in doc.children() {
+            combined.append(from_owned_tree(&to_owned_tree(block), arena));
+        }
+    }
+    combined
+}
+
+/// Turns bare tokens in text that satisfy `predicate`, such as relative
+/// paths ending in `.md`, into links pointing at themselves.
+///
+/// Tokens are runs of non-whitespace, with surrounding brackets, quotes
+/// and trailing punctuation stripped before `predicate` sees them, so
+/// `(see docs/setup.md).` links just `docs/setup.md`. Text already inside
+/// a link or image is skipped, and code spans hold no text nodes, so
+/// their contents are never linked either.
+pub fn autolink_paths<'a>(
+    root: &'a AstNode<'a>,
+    predicate: impl Fn(&str) -> bool,
+    arena: &'a Arena<AstNode<'a>>,
+) {
+    let texts: Vec<_> = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Text(_)))
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| {
+                matches!(
+                    ancestor.data.borrow().value,
+                    NodeValue::Link(_) | NodeValue::Image(_) | NodeValue::WikiLink(_)
+                )
+            })
+        })
+        .collect();
+
+    for node in texts {
+        let NodeValue::Text(text) = node.data.borrow().value.clone() else {
+            continue;
+        };
+        let paths = find_paths(&text, &predicate);
+        if paths.is_empty() {
+            continue;
+        }
+
+        let mut end = 0;
+        for path in paths {
+            if path.start > end {
+                let before = NodeValue::Text(text[end..path.start].to_string());
+                node.insert_before(arena.alloc(AstNode::from(before)));
+            }
+            let url = text[path.clone()].to_string();
+            let link = arena.alloc(AstNode::from(NodeValue::Link(NodeLink {
+                url: url.clone(),
+                title: String::new(),
+            })));
+            link.append(arena.alloc(AstNode::from(NodeValue::Text(url))));
+            node.insert_before(link);
+            end = path.end;
+        }
+        if end < text.len() {
+            node.insert_before(
+                arena.alloc(AstNode::from(NodeValue::Text(text[end..].to_string()))),
+            );
+        }
+        node.detach();
+    }
+}
+
+/// Returns the byte ranges of the tokens in `text` that satisfy `predicate`.
+fn find_paths(text: &str, predicate: &impl Fn(&str) -> bool) -> Vec<Range<usize>> {
+    let mut paths = Vec::new();
+    let mut offset = 0;
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += piece.len();
+        let token = piece.trim_end_matches(char::is_whitespace);
+
+        let leading = token.len() - token.trim_start_matches(['(', '[', '"', '\'']).len();
+        let path =
+            token[leading..].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '"', '\'']);
+        if !path.is_empty() && predicate(path) {
+            paths.push(start + leading..start + leading + path.len());
+        }
+    }
+    paths
+}
+
+/// Collapses every run of blank lines in `source` to at most
+/// `max_consecutive` lines.
+///
+/// Blank lines inside fenced code blocks are significant and left
+/// untouched. Note that `max_consecutive` of 0 merges paragraphs that
+/// were only separated by blank lines.
+pub fn normalize_blank_lines(source: &str, max_consecutive: usize) -> String {
+    normalize_blank_lines_with(source, max_consecutive, false)
+}
+
+/// Like `normalize_blank_lines`, but with `trim_trailing_whitespace` also
+/// strips trailing whitespace from every line outside fenced code blocks.
+///
+/// Beware that two trailing spaces are a hard line break in Markdown,
+/// so trimming turns them into a plain line break.
+pub fn normalize_blank_lines_with(
+    source: &str,
+    max_consecutive: usize,
+    trim_trailing_whitespace: bool,
+) -> String {
+    let mut normalized = String::with_capacity(source.len());
+    let mut fence: Option<(char, usize)> = None;
+    let mut blank_run = 0;
+
+    for line in source.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        // Fence lines themselves are treated like prose.
+        let in_code = match fence {
+            Some((marker, len)) => {
+                let closes = fence_marker(text).is_some_and(|(c, n)| c == marker && n >= len);
+                if closes {
+                    fence = None;
+                }
+                !closes
+            }
+            None => {
+                fence = fence_marker(text);
+                false
+            }
+        };
+        if in_code {
+            normalized.push_str(line);
+            continue;
+        }
+
+        if text.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > max_consecutive {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+
+        if trim_trailing_whitespace {
+            normalized.push_str(text.trim_end());
+            normalized.push_str(&line[text.len()..]);
+        } else {
+            normalized.push_str(line);
+        }
+    }
+
+    normalized
+}
+
+/// Rewraps the paragraphs in `source` to `width` columns.
+///
+/// Paragraphs are found by parsing the source as GFM, so code blocks,
+/// tables, headings, HTML and front matter are copied through as they
+/// are. Paragraphs inside list items and blockquotes keep their markers,
+/// and continuation lines are indented to match. Hard line breaks, two
+/// trailing spaces or a backslash, stay where they are. Words longer
+/// than `width` get a line of their own, and a word that would start a
+/// new block at the beginning of a line, such as `-` or `1.`, is kept on
+/// the line before instead, even past `width`.
+///
+/// Runs of whitespace between words collapse to a single space. Code
+/// spans, autolinks and inline HTML are never broken and keep the
+/// whitespace inside them.
+pub fn reflow(source: &str, width: usize) -> String {
+    let flavor = ParseConfig::builder("")
+        .flavor(Flavor::GitHub)
+        .front_matter_delimiter(Some("---".to_string()))
+        .build()
+        .as_flavor();
+    let arena = Arena::new();
+    let root = extract_ast_from_str(source, flavor, &arena);
+
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut reflowed = String::with_capacity(source.len());
+    let mut next_line = 0;
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        if !matches!(data.value, NodeValue::Paragraph) {
+            continue;
+        }
+        let start = data.sourcepos.start.line - 1;
+        let end = data.sourcepos.end.line;
+        if start < next_line || end > lines.len() {
+            continue;
+        }
+
+        for line in &lines[next_line..start] {
+            reflowed.push_str(line);
+        }
+        reflow_paragraph(
+            &lines[start..end],
+            data.sourcepos.start.column - 1,
+            width,
+            &mut reflowed,
+        );
+        next_line = end;
+    }
+    for line in &lines[next_line..] {
+        reflowed.push_str(line);
+    }
+
+    reflowed
+}
+
+/// Rewraps the source `lines` of one paragraph whose text starts at byte
+/// `column` of the first line, appending them to `out`.
+fn reflow_paragraph(lines: &[&str], column: usize, width: usize, out: &mut String) {
+    let last = lines[lines.len() - 1];
+    let ending = &last[last.trim_end_matches(['\n', '\r']).len()..];
+    let newline = if lines[0].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let first_prefix = lines[0].get(..column).unwrap_or("");
+    // Blockquote markers repeat on every line, list markers become indentation.
+    let prefix: String = first_prefix
+        .chars()
+        .map(|c| if c == '>' { '>' } else { ' ' })
+        .collect();
+
+    // Continuation lines lose their indentation like the parser strips
+    // it, so joining them with `\n` keeps what the inline parser sees.
+    let text = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.trim_end_matches(['\n', '\r']);
+            if i == 0 {
+                line.get(column..).unwrap_or(line)
+            } else {
+                line.trim_start_matches(|c: char| c == '>' || c.is_whitespace())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let prefix_width = prefix.chars().count();
+    let mut current = first_prefix.to_string();
+    let mut current_width = first_prefix.chars().count();
+    let mut has_words = false;
+    for (word, hard_break) in reflow_tokens(&text) {
+        let word_width = word.chars().count();
+        if has_words {
+            if current_width + 1 + word_width > width && !starts_block(&word) {
+                out.push_str(&current);
+                out.push_str(newline);
+                current.clone_from(&prefix);
+                current_width = prefix_width;
+            } else {
+                current.push(' ');
+                current_width += 1;
+            }
+        }
+        current.push_str(&word);
+        current_width += word_width;
+        has_words = true;
+
+        if let Some(marker) = hard_break {
+            out.push_str(&current);
+            out.push_str(marker);
+            out.push_str(newline);
+            current.clone_from(&prefix);
+            current_width = prefix_width;
+            has_words = false;
+        }
+    }
+    out.push_str(&current);
+    out.push_str(ending);
+}
+
+/// Splits paragraph text into the words `reflow` may break lines between,
+/// each with the hard line break marker that follows it, if any.
+///
+/// Code spans, autolinks and inline HTML are single words, whatever
+/// whitespace they contain. A line ending inside one becomes a space,
+/// which is how the inline parser reads it anyway.
+fn reflow_tokens(text: &str) -> Vec<(String, Option<&'static str>)> {
+    let bytes = text.as_bytes();
+    let mut tokens: Vec<(String, Option<&'static str>)> = Vec::new();
+    let mut i = 0;
+    loop {
+        let gap_start = i;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let gap = &text[gap_start..i];
+        if let Some(newline) = gap.find('\n')
+            && let Some((word, hard_break)) = tokens.last_mut()
+        {
+            let backslashes = word.len() - word.trim_end_matches('\\').len();
+            if gap[..newline].ends_with("  ") {
+                *hard_break = Some("  ");
+            } else if newline == 0 && backslashes % 2 == 1 {
+                // The backslash is already the end of the word.
+                *hard_break = Some("");
+            }
+        }
+        if i == bytes.len() {
+            return tokens;
+        }
+
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+            i = match bytes[i] {
+                // An escaped backtick or `<` opens nothing.
+                b'\\' if matches!(bytes.get(i + 1), Some(b'`' | b'<')) => i + 2,
+                b'`' => {
+                    let run = text[i..].len() - text[i..].trim_start_matches('`').len();
+                    closing_backticks(text, i + run, run).unwrap_or(i + run)
+                }
+                b'<' => inline_markup_end(text, i).unwrap_or(i + 1),
+                _ => i + 1,
+            };
+        }
+        tokens.push((text[start..i].replace('\n', " "), None));
+    }
+}
+
+/// Returns the end of the first run of exactly `run` backticks at or
+/// after `from`, which closes a code span.
+fn closing_backticks(text: &str, from: usize, run: usize) -> Option<usize> {
+    let mut i = from;
+    while let Some(offset) = text[i..].find('`') {
+        let start = i + offset;
+        let len = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if len == run {
+            return Some(start + len);
+        }
+        i = start + len;
+    }
+    None
+}
+
+/// Returns the end of the autolink or inline HTML tag starting at the
+/// `<` at `start`, if there is one.
+fn inline_markup_end(text: &str, start: usize) -> Option<usize> {
+    let rest = &text[start + 1..];
+    let inner = &rest[..rest.find('>')?];
+    if inner.contains('<') {
+        return None;
+    }
+    let autolink =
+        !inner.is_empty() && !inner.contains(char::is_whitespace) && inner.contains([':', '@']);
+    let tag = inner.starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'));
+    (autolink || tag).then_some(start + 1 + inner.len() + 1)
+}
+
+/// Returns whether `word` at the start of a line could open a new block
+/// or turn the paragraph into a setext heading.
+fn starts_block(word: &str) -> bool {
+    let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    word.chars()
+        .all(|c| matches!(c, '-' | '+' | '*' | '_' | '='))
+        || word.chars().all(|c| c == '#')
+        || word.starts_with(['>', '<', '|'])
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || ((1..=9).contains(&digits) && matches!(&word[digits..], "." | ")"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{extract_images, extract_links};
+    use crate::renderer::to_html;
+
+    #[test]
+    fn test_rewrite_image_urls() {
+        let arena = Arena::new();
+        let md = "![a](./a.png) ![b](https://other/b.png) ![c](img/c.png) ![d](/img/d.png) \
+                  ![e](//host/e.png) ![f](#f) ![g]()";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        rewrite_image_urls(root, "https://cdn.example/");
+        let urls: Vec<_> = extract_images(root)
+            .into_iter()
+            .map(|image| image.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://cdn.example/a.png",
+                "https://other/b.png",
+                "https://cdn.example/img/c.png",
+                "/img/d.png",
+                "//host/e.png",
+                "#f",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let arena = Arena::new();
+        let md = "Before.\n\n<!-- note -->\n\nAfter <!-- inline --> text.\n\n\
+                  <div><!-- kept --></div>\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        strip_comments(root);
+        let mut options = Flavor::CommonMark.to_options();
+        options.render.unsafe_ = true;
+        assert_eq!(
+            to_html(root, Flavor::Custom(options)),
+            "<p>Before.</p>\n<p>After  text.</p>\n<div><!-- kept --></div>\n"
+        );
+    }
+
+    #[test]
+    fn test_number_headings() {
+        let arena = Arena::new();
+        let md = "# Intro\n\n## Scope\n\n## *Terms*\n\n### Glossary\n\n# Usage\n\n### Deep\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        number_headings(root);
+        let texts: Vec<_> = extract_headings(root).into_iter().map(|h| h.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "1 Intro",
+                "1.1 Scope",
+                "1.2 Terms",
+                "1.2.1 Glossary",
+                "2 Usage",
+                "2.1 Deep",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inject_toc() {
+        let arena = Arena::new();
+        let md = "# Guide\n\n[[TOC]]\n\n## Install\n\nText.\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        inject_toc(root, Flavor::CommonMark, &arena);
+        assert!(!to_plain_text(root).contains("TOC"));
+        let urls: Vec<_> = extract_links(root)
+            .into_iter()
+            .map(|link| link.url)
+            .collect();
+        assert_eq!(urls, vec!["#guide", "#install"]);
+
+        let second = root.children().nth(1).unwrap();
+        assert!(matches!(second.data.borrow().value, NodeValue::List(_)));
+    }
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn test_replace_emoji_shortcodes() {
+        assert!(
+            EMOJI_SHORTCODES
+                .windows(2)
+                .all(|pair| pair[0].0 < pair[1].0)
+        );
+
+        let arena = Arena::new();
+        let md = "Launch :rocket::tada: but :notacode: and 10:30: stay.\n\n`:rocket:`\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        replace_emoji_shortcodes(root);
+        assert_eq!(
+            to_html(root, Flavor::CommonMark),
+            "<p>Launch 🚀🎉 but :notacode: and 10:30: stay.</p>\n\
+             <p><code>:rocket:</code></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_lists() {
+        let arena = Arena::new();
+        let md = "* one\n* two\n  + nested\n  + more\n\n5. five\n6. six\n   - deep\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        normalize_lists(root, '*', true);
+        assert_eq!(
+            crate::renderer::to_markdown(root, Flavor::CommonMark),
+            "* one\n* two\n  * nested\n  * more\n\n<!-- end list -->\n\n1. five\n2. six\n   * deep\n"
+        );
+
+        // Without renumbering ordered lists keep their start.
+        let root = extract_ast_from_str("3. a\n4. b\n", Flavor::CommonMark, &arena);
+        normalize_lists(root, '-', false);
+        assert_eq!(
+            crate::renderer::to_markdown(root, Flavor::CommonMark),
+            "3. a\n4. b\n"
+        );
+    }
+
+    #[test]
+    fn test_concat_documents() {
+        let arena = Arena::new();
+        let first = extract_ast_from_str("# First\n", Flavor::CommonMark, &arena);
+        let second = extract_ast_from_str("# Second\n", Flavor::CommonMark, &arena);
+
+        let combined = concat_documents(&[first, second], false, &arena);
+        let texts: Vec<_> = extract_headings(combined)
+            .into_iter()
+            .map(|h| h.text)
+            .collect();
+        assert_eq!(texts, vec!["First", "Second"]);
+        assert_eq!(
+            crate::renderer::to_markdown(combined, Flavor::CommonMark),
+            "# First\n\n# Second\n"
+        );
+
+        // The inputs are copied, not moved.
+        assert_eq!(first.children().count(), 1);
+        number_headings(combined);
+        assert_eq!(to_plain_text(first), "First");
+
+        let separated = concat_documents(&[first, second], true, &arena);
+        let second_block = separated.children().nth(1).unwrap();
+        assert!(matches!(
+            second_block.data.borrow().value,
+            NodeValue::ThematicBreak
+        ));
+        assert_eq!(separated.children().count(), 3);
+    }
+
+    #[test]
+    fn test_autolink_paths() {
+        let arena = Arena::new();
+        let md = "see docs/setup.md, `code.md` and [the guide](guide.md) (or my_notes.md).\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+        autolink_paths(root, |token| token.ends_with(".md"), &arena);
+
+        let urls: Vec<String> = extract_links(root)
+            .into_iter()
+            .map(|link| link.url)
+            .collect();
+        assert_eq!(urls, ["docs/setup.md", "guide.md", "my_notes.md"]);
+        assert_eq!(
+            to_html(root, Flavor::CommonMark),
+            "<p>see <a href=\"docs/setup.md\">docs/setup.md</a>, <code>code.md</code> and \
+             <a href=\"guide.md\">the guide</a> (or <a href=\"my_notes.md\">my_notes.md</a>).</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_reflow() {
+        let source = "# A heading that is much longer than forty columns\n\n\
+                      This paragraph is long enough that it has to be wrapped \
+                      over several lines.\nIts second source line joins in.\n\n\
+                      ```\nlet code = \"a line of code that is longer than forty columns\";\n```\n\n\
+                      - A list item whose text runs well past the forty column limit\n\n\
+                      > Quoted text with a hard break  \n> right here, and a backslash\\\nbreak too.\n";
+
+        assert_eq!(
+            reflow(source, 40),
+            "# A heading that is much longer than forty columns\n\n\
+             This paragraph is long enough that it\nhas to be wrapped over several lines.\n\
+             Its second source line joins in.\n\n\
+             ```\nlet code = \"a line of code that is longer than forty columns\";\n```\n\n\
+             - A list item whose text runs well past\n  the forty column limit\n\n\
+             > Quoted text with a hard break  \n> right here, and a backslash\\\n> break too.\n"
+        );
+        // Spans are never broken, and a `<` can't start a line.
+        assert_eq!(
+            reflow(
+                "word `code with  two spaces` and\n`a\nspan` <span class=\"x y\">z</span>\n",
+                20
+            ),
+            "word\n`code with  two spaces`\nand `a span` <span class=\"x y\">z</span>\n"
+        );
+        // A word that would start a list stays on the line before.
+        assert_eq!(reflow("one two - three\n", 8), "one two -\nthree\n");
+    }
+
+    #[test]
+    fn test_normalize_blank_lines() {
+        let source = "Intro  \n\n\n\nBefore code\n\n```rust\nfn a() {}\n\n\n\nfn b() {}  \n```\n\n\n\nOutro\n";
+
+        assert_eq!(
+            normalize_blank_lines(source, 1),
+            "Intro  \n\nBefore code\n\n```rust\nfn a() {}\n\n\n\nfn b() {}  \n```\n\nOutro\n"
+        );
+        assert_eq!(
+            normalize_blank_lines_with(source, 2, true),
+            "Intro\n\n\nBefore code\n\n```rust\nfn a() {}\n\n\n\nfn b() {}  \n```\n\n\nOutro\n"
+        );
+        assert_eq!(normalize_blank_lines("a\r\n \r\n\r\nb", 1), "a\r\n \r\nb");
+    }
+
+    fn heading_levels<'a>(root: &'a AstNode<'a>) -> Vec<u8> {
+        extract_headings(root)
+            .into_iter()
+            .map(|h| h.level)
+            .collect()
+    }
+
+    #[test]
+    fn test_shift_heading_levels() {
+        let arena = Arena::new();
+        let md = "# One\n\n## Two\n\n### Three\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        shift_heading_levels(root, 1);
+        assert_eq!(heading_levels(root), vec![2, 3, 4]);
+        shift_heading_levels(root, -1);
+        assert_eq!(heading_levels(root), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_shift_heading_levels_clamps() {
+        let arena = Arena::new();
+        let md = "# Top\n\n###### Bottom\n";
+        let root = extract_ast_from_str(md, Flavor::CommonMark, &arena);
+
+        shift_heading_levels(root, 1);
+        assert_eq!(heading_levels(root), vec![2, 6]);
+        shift_heading_levels(root, -100);
+        assert_eq!(heading_levels(root), vec![1, 1]);
+        shift_heading_levels(root, 127);
+        assert_eq!(heading_levels(root), vec![6, 6]);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mkforge-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("parts")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let dir = temp_dir("includes");
+        let main = dir.join("main.md");
+        fs::write(
+            &main,
+            "# Guide\n\n{% include \"parts/intro.md\" %}\n\nThe end.\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("parts/intro.md"),
+            "Intro text.\n\n{% include 'more.md' %}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("parts/more.md"), "- nested\n").unwrap();
+
+        let arena = Arena::new();
+        let root = parse(&ParseConfig::new(&main, Flavor::CommonMark), &arena)
+            .unwrap()
+            .root;
+        resolve_includes(&main, root, Flavor::CommonMark, 2, &arena).unwrap();
+        assert_eq!(to_plain_text(root), "Guide\nIntro text.\nnested\nThe end.");
+
+        let root = parse(&ParseConfig::new(&main, Flavor::CommonMark), &arena)
+            .unwrap()
+            .root;
+        let err = resolve_includes(&main, root, Flavor::CommonMark, 1, &arena).unwrap_err();
+        assert!(matches!(
+            err,
+            MkforgeError::IncludeTooDeep { max_depth: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_resolve_includes_errors() {
+        let dir = temp_dir("include-cycle");
+        let a = dir.join("a.md");
+        fs::write(&a, "{% include \"parts/b.md\" %}\n").unwrap();
+        fs::write(dir.join("parts/b.md"), "{% include \"../a.md\" %}\n").unwrap();
+        fs::write(dir.join("missing.md"), "{% include \"nope.md\" %}\n").unwrap();
+
+        let arena = Arena::new();
+        let root = parse(&ParseConfig::new(&a, Flavor::CommonMark), &arena)
+            .unwrap()
+            .root;
+        let err = resolve_includes(&a, root, Flavor::CommonMark, 10, &arena).unwrap_err();
+        assert!(matches!(err, MkforgeError::IncludeCycle { ref path } if path.ends_with("a.md")));
+
+        let missing = dir.join("missing.md");
+        let root = parse(&ParseConfig::new(&missing, Flavor::CommonMark), &arena)
+            .unwrap()
+            .root;
+        let err = resolve_includes(&missing, root, Flavor::CommonMark, 10, &arena).unwrap_err();
+        assert!(
+            matches!(err, MkforgeError::ReadFile { ref path, .. } if path.ends_with("nope.md"))
+        );
+    }
+
+    #[test]
+    fn test_flatten_emphasis() {
+        let arena = Arena::new();
+        let md = "**bold _italic_** and ~~gone [link](https://a.example)~~\n";
+        let root = extract_ast_from_str(md, Flavor::GitHub, &arena);
+
+        flatten_emphasis(root);
+        assert_eq!(
+            to_html(root, Flavor::GitHub),
+            "<p>bold italic and gone <a href=\"https://a.example\">link</a></p>\n"
+        );
+    }
+}
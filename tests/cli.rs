@@ -0,0 +1,134 @@
+use assert_cmd::Command;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mkforge-cli-{}-{}.md", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn mkforge(args: &[&str]) -> std::process::Output {
+    Command::cargo_bin("mkforge")
+        .unwrap()
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_parse_prints_node_count() {
+    let path = write_temp("parse", "# Title\n\nSome text.\n");
+    let output = mkforge(&["parse", path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    // document, heading, text, paragraph, text
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5 nodes\n");
+}
+
+#[test]
+fn test_parse_json() {
+    let path = write_temp("json", "# Title\n");
+    let output = mkforge(&["parse", path.to_str().unwrap(), "--json"]);
+
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["type"], "document");
+    assert_eq!(json["children"][0]["level"], 1);
+}
+
+#[test]
+fn test_toc_and_links() {
+    let path = write_temp("toc", "# Guide\n\n## Install\n\nSee https://a.example.\n");
+    let file = path.to_str().unwrap();
+
+    let output = mkforge(&["toc", file]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "- [Guide](#guide)\n  - [Install](#install)\n"
+    );
+
+    let output = mkforge(&["links", file, "--flavor", "gfm"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://a.example\n"
+    );
+}
+
+#[test]
+fn test_reads_stdin() {
+    let output = Command::cargo_bin("mkforge")
+        .unwrap()
+        .args(["links", "-", "--flavor", "gfm"])
+        .write_stdin("# Title\n\nSee https://a.example and [docs](docs.md).\n")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://a.example\ndocs.md\n"
+    );
+
+    let output = Command::cargo_bin("mkforge")
+        .unwrap()
+        .args(["toc", "-"])
+        .write_stdin("# Title\n\n## Part\n")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "- [Title](#title)\n  - [Part](#part)\n"
+    );
+}
+
+#[test]
+fn test_errors_exit_non_zero() {
+    let path = write_temp("errors", "text\n");
+    let output = mkforge(&["parse", path.to_str().unwrap(), "--flavor", "nope"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: unknown Markdown flavor `nope`\n"
+    );
+
+    let output = mkforge(&["toc", "/no/such/file.md"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .starts_with("error: failed to read `/no/such/file.md`")
+    );
+}
+
+#[test]
+fn test_watch_reparses_on_write() {
+    let path = write_temp("watch", "# One\n");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("mkforge"))
+        .args(["watch", "toc", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    thread::spawn(move || {
+        for line in stdout.lines().map_while(Result::ok) {
+            let _ = tx.send(line);
+        }
+    });
+    let next_line = || rx.recv_timeout(Duration::from_secs(10)).unwrap();
+
+    assert_eq!(next_line(), "- [One](#one)");
+    fs::write(&path, "# One\n\n# Two\n").unwrap();
+    assert!(next_line().ends_with(&format!("re-parsed {}", path.display())));
+    assert_eq!(next_line(), "- [One](#one)");
+    assert_eq!(next_line(), "- [Two](#two)");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}